@@ -1,15 +1,17 @@
-use std::{fs, path::Path, process::{self, Command}};
+use std::{fs, io::{self, IsTerminal}, path::Path, process::{self, Command}};
 
 use clap::{Arg, Command as ClapCommand};
 use inkwell::context::Context;
 
+use diagnostics::{codes, json, Diagnostic, Renderer, SourceMap};
 use lexer::Lexer;
 use parser::parser::Parser;
+use parser::semantic::SemanticChecker;
 use parser::type_check::TypeChecker;
 use codegen::CodeGen;
 
-fn main() {
-    let matches = ClapCommand::new("hydrac")
+fn cli() -> ClapCommand {
+    ClapCommand::new("hydrac")
         .version(env!("CARGO_PKG_VERSION"))
         .about("Hydra Compiler CLI")
         .arg(
@@ -37,41 +39,37 @@ fn main() {
                 .help("Emir llvm ir to a .ir file")
                 .action(clap::ArgAction::SetTrue)
         )
-        .get_matches();
+        .arg(
+            Arg::new("unchecked")
+                .long("unchecked")
+                .help("Leave out runtime array bounds checks")
+                .action(clap::ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("color")
+                .long("color")
+                .help("Coloring of diagnostics")
+                .value_name("WHEN")
+                .value_parser(["auto", "always", "never"])
+                .default_value("auto")
+        )
+        .arg(
+            Arg::new("error-format")
+                .long("error-format")
+                .help("How diagnostics are printed")
+                .value_name("FORMAT")
+                .value_parser(["human", "json"])
+                .default_value("human")
+        )
+}
+
+fn main() {
+    let matches = cli().get_matches();
 
     let input = match matches.get_one::<String>("input") {
         Some(i) => i,
         None => {
-            ClapCommand::new("hydrac")
-                .version(env!("CARGO_PKG_VERSION"))
-                .about("Hydra Compiler CLI")
-                .arg(
-                    Arg::new("input")
-                        .help("Input .hydra source file")
-                        .required(false)
-                        .value_name("INPUT")
-                        .index(1),
-                )
-                .arg(
-                    Arg::new("tokens")
-                        .long("tokens")
-                        .help("Emit tokens to a .tokens file")
-                        .action(clap::ArgAction::SetTrue),
-                )
-                .arg(
-                    Arg::new("ast")
-                        .long("ast")
-                        .help("Emit AST nodes to a .nodes file")
-                        .action(clap::ArgAction::SetTrue)
-                )
-                .arg(
-                    Arg::new("ir")
-                        .long("ir")
-                        .help("Emir llvm ir to a .ir file")
-                        .action(clap::ArgAction::SetTrue)
-                )
-                .print_help()
-                .unwrap();
+            cli().print_help().unwrap();
             println!();
             process::exit(0)
         }
@@ -80,6 +78,17 @@ fn main() {
     let emit_tokens = matches.get_flag("tokens");
     let emit_ast = matches.get_flag("ast");
     let emit_ir = matches.get_flag("ir");
+    let unchecked = matches.get_flag("unchecked");
+    let color = match matches.get_one::<String>("color").map(String::as_str) {
+        Some("always") => true,
+        Some("never") => false,
+        _ => io::stderr().is_terminal(),
+    };
+    let reporter = Reporter {
+        json: matches.get_one::<String>("error-format").is_some_and(|f| f == "json"),
+        color,
+    };
+    let mut source_map = SourceMap::new();
     let input_path = Path::new(input);
 
     // --- Get the file stem for naming the module and output file ---
@@ -92,7 +101,8 @@ fn main() {
     match input_path.extension().and_then(|e| e.to_str()) {
         Some("hydra") => {}
         _ => {
-            eprintln!("error: '{}' is not a .hydra file", input);
+            let message = format!("'{}' is not a .hydra file", input);
+            reporter.report(&source_map, &Diagnostic::error(codes::INVALID_INPUT, message));
             process::exit(1);
         }
     }
@@ -101,20 +111,24 @@ fn main() {
     let contents = match fs::read_to_string(input) {
         Ok(c) => c,
         Err(e) => {
-            eprintln!("error reading '{}': {}", input, e);
+            let message = format!("reading '{}' failed: {}", input, e);
+            reporter.report(&source_map, &Diagnostic::error(codes::IO, message));
             process::exit(1);
         }
     };
 
-    // Run lexer
-    let mut lexer = Lexer::new(&contents);
-    let tokens = match lexer.tokenize() {
-        Ok(t) => t,
-        Err(e) => {
-            eprintln!("lexer error: {}", e);
-            process::exit(1);
+    let file_id = source_map.add_file(input.as_str(), contents);
+    let report = |diagnostic: &Diagnostic| reporter.report(&source_map, diagnostic);
+
+    // Run lexer, reporting every lexical error before giving up
+    let mut lexer = Lexer::with_file_id(source_map.source(file_id), file_id);
+    let (tokens, lex_errors) = lexer.tokenize_recovering();
+    if !lex_errors.is_empty() {
+        for e in lex_errors {
+            report(&e.into());
         }
-    };
+        process::exit(1);
+    }
 
     // Emit tokens to a file if requested
     if emit_tokens {
@@ -125,7 +139,7 @@ fn main() {
             .join("\n");
         let token_filename = input_path.with_extension("tokens").to_string_lossy().into_owned();
         if let Err(e) = fs::write(&token_filename, token_output) {
-            eprintln!("error: writing token file '{}' failed: {}", token_filename, e);
+            report(&Diagnostic::error(codes::IO, format!("writing token file '{}' failed: {}", token_filename, e)));
             process::exit(1);
         }
         println!("Tokens written to: {}", token_filename);
@@ -137,7 +151,7 @@ fn main() {
     let ast = match parser.parse() {
         Ok(ast) => ast,
         Err(e) => {
-            eprintln!("parser error: {}", e);
+            report(&e);
             process::exit(1);
         }
     };
@@ -150,7 +164,7 @@ fn main() {
             .join("\n\n");
         let ast_filename = input_path.with_extension("nodes").to_string_lossy().into_owned();
         if let Err(e) = fs::write(&ast_filename, ast_output) {
-            eprintln!("error: writing AST file '{}': {}", ast_filename, e);
+            report(&Diagnostic::error(codes::IO, format!("writing AST file '{}' failed: {}", ast_filename, e)));
             process::exit(1);
         }
         println!("AST written to: {}", ast_filename);
@@ -158,16 +172,28 @@ fn main() {
     }
 
     let mut type_checker = TypeChecker::new();
-    if let Err(e) = type_checker.check(&ast) {
-        eprintln!("error: {}", e);
+    if let Err(errors) = type_checker.check(&ast) {
+        for e in errors {
+            report(&e);
+        }
         process::exit(1);
     };
 
+    let mut semantic_checker = SemanticChecker::new(type_checker.match_types());
+    let semantic_result = semantic_checker.check(&ast);
+    for warning in semantic_checker.warnings() {
+        report(warning);
+    }
+    if let Err(e) = semantic_result {
+        report(&e);
+        process::exit(1);
+    }
+
     let context = Context::create();
-    let mut codegen = CodeGen::new(&context, module_name);
+    let mut codegen = CodeGen::new(&context, module_name, &source_map, type_checker.literal_types()).with_bounds_checks(!unchecked);
 
     if let Err(e) = codegen.generate(&ast) {
-        eprintln!("codegen error: {}", e);
+        report(&e);
         process::exit(1);
     }
 
@@ -176,7 +202,7 @@ fn main() {
         let ir_filename = input_path.with_extension("ll").to_string_lossy().into_owned();
 
         if let Err(e) = fs::write(&ir_filename, ir_output) {
-            eprintln!("error: writing IR to .ll file '{}' failed: {}", ir_filename, e);
+            report(&Diagnostic::error(codes::IO, format!("writing IR to .ll file '{}' failed: {}", ir_filename, e)));
             process::exit(1);
         }
         println!("IR written to: {}", ir_filename);
@@ -187,7 +213,7 @@ fn main() {
     let obj_path_str = format!("{}.o", module_name);
     let obj_path = Path::new(&obj_path_str);
     if let Err(e) = codegen.write_to_object_file(obj_path) {
-        eprintln!("error: writing object file: {} failed", e);
+        report(&e);
         process::exit(1);
     }
 
@@ -197,19 +223,41 @@ fn main() {
         .arg("-o")
         .arg(module_name)
         .arg("-O2")
-        .output()
-        .expect("error: failed to execute linker");
+        .output();
+
+    let linker_output = match linker_output {
+        Ok(output) => output,
+        Err(e) => {
+            report(&Diagnostic::error(codes::LINKER, format!("failed to execute linker 'clang': {}", e)));
+            process::exit(1);
+        }
+    };
 
     if !linker_output.status.success() {
-        eprintln!(
-            "linker error:\n{}",
-            String::from_utf8_lossy(&linker_output.stderr)
-        );
+        let message = format!("linking '{}' failed", module_name);
+        report(&Diagnostic::error(codes::LINKER, message)
+            .with_note(String::from_utf8_lossy(&linker_output.stderr).trim_end()));
         process::exit(1);
     }
 
     // Clean up the temporary object file
     if let Err(e) = fs::remove_file(obj_path) {
-        eprintln!("warning: could not remove temporary object file: {}", e);
+        let message = format!("could not remove temporary object file: {}", e);
+        report(&Diagnostic::warning(codes::IO, message));
+    }
+}
+
+struct Reporter {
+    json: bool,
+    color: bool,
+}
+
+impl Reporter {
+    fn report(&self, source_map: &SourceMap, diagnostic: &Diagnostic) {
+        if self.json {
+            eprintln!("{}", json::to_json(diagnostic, source_map));
+        } else {
+            eprintln!("{}", Renderer::new(source_map, self.color).render(diagnostic));
+        }
     }
 }
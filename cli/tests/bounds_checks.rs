@@ -0,0 +1,73 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// A directory of its own under the system's temporary directory, removed
+/// again when dropped.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("hydra-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+
+    fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Compiles `source` to IR with `hydrac` and runs it with `lli`, which comes
+/// with the LLVM the compiler builds against.
+fn run(name: &str, source: &str) -> Output {
+    let dir = TempDir::new(name);
+    fs::write(dir.path().join(format!("{}.hydra", name)), source).unwrap();
+
+    let compiled = Command::new(env!("CARGO_BIN_EXE_hydrac"))
+        .arg(format!("{}.hydra", name))
+        .arg("--ir")
+        .current_dir(dir.path())
+        .output()
+        .unwrap();
+    assert!(compiled.status.success(), "{}", String::from_utf8_lossy(&compiled.stderr));
+
+    Command::new("lli")
+        .arg(format!("{}.ll", name))
+        .current_dir(dir.path())
+        .output()
+        .unwrap_or_else(|e| panic!("running `lli` failed, is LLVM's `lli` on the PATH? {}", e))
+}
+
+#[test]
+fn out_of_bounds_index_panics_with_its_location() {
+    let source = "fn main() -> void {
+    let a: [i32, 5] = { 1, 2, 3, 4, 5 };
+    let k: u8 = 7;
+    println(\"{}\", a[k]);
+}
+";
+    let output = run("unsigned", source);
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "index 7 out of bounds for length 5 at unsigned.hydra:4:19\n");
+    assert_eq!(output.status.code(), Some(101));
+    assert!(output.stdout.is_empty());
+
+    let source = "fn get(numbers: [i32, anysize], i: i64) -> i32 {
+    return numbers[i];
+}
+
+fn main() -> void {
+    let a: [i32, 5] = { 1, 2, 3, 4, 5 };
+    println(\"{}\", get(a, -2));
+}
+";
+    let output = run("signed", source);
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "index -2 out of bounds for length 5 at signed.hydra:2:12\n");
+    assert_eq!(output.status.code(), Some(101));
+}
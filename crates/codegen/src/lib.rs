@@ -2,25 +2,94 @@ use std::collections::HashMap;
 
 use inkwell::targets::{InitializationConfig, Target, TargetMachine};
 use inkwell::OptimizationLevel;
+use inkwell::basic_block::BasicBlock;
 use inkwell::builder::Builder;
 use inkwell::context::Context;
 use inkwell::module::Module;
 use inkwell::values::{BasicValue, BasicValueEnum, FunctionValue, PointerValue, BasicMetadataValueEnum};
 use inkwell::types::{BasicType, BasicTypeEnum};
 
+use diagnostics::{codes, Diagnostic, SourceMap, Span};
 use lexer::{Token, TokenType};
-use parser::ast::ASTNode;
+use parser::ast::{ASTNode, MatchArm, Pattern, TypeExpr};
+use parser::types::Type;
+
+/// A generated value together with its Hydra type, which decides between the
+/// signed and unsigned forms of instructions.
+#[derive(Debug, Clone)]
+struct Value<'ctx> {
+    value: BasicValueEnum<'ctx>,
+    ty: Type,
+}
 
 pub struct CodeGen<'ctx> {
     context: &'ctx Context,
     builder: Builder<'ctx>,
     module: Module<'ctx>,
-    named_values: HashMap<String, PointerValue<'ctx>>,
+    named_values: HashMap<String, (PointerValue<'ctx>, Type)>,
     current_function: Option<FunctionValue<'ctx>>,
+    current_return_type: Option<Type>,
+    /// The innermost loop last.
+    loops: Vec<LoopTargets<'ctx>>,
+    /// Parameter and return types of every function, by name.
+    signatures: HashMap<String, (Vec<Type>, Type)>,
+    /// For locations in runtime error messages.
+    source_map: &'ctx SourceMap,
+    /// The type the type checker gave each unsuffixed literal expression, by
+    /// its span.
+    literal_types: &'ctx HashMap<Span, Type>,
+    bounds_checks: bool,
+    /// The values a variable is known to stay within, by its alloca, such as
+    /// for the variable of a `for` loop with constant bounds.
+    index_ranges: HashMap<PointerValue<'ctx>, (i128, i128)>,
+}
+
+/// The runtime routine a failed bounds check calls. It prints the index, the
+/// length and the location, then exits with `BOUNDS_PANIC_EXIT_CODE`.
+const BOUNDS_PANIC: &str = "hydra.panic_bounds";
+const BOUNDS_PANIC_EXIT_CODE: u64 = 101;
+
+/// Where `continue` and `break` jump to in a loop.
+#[derive(Debug, Clone, Copy)]
+struct LoopTargets<'ctx> {
+    continue_block: BasicBlock<'ctx>,
+    break_block: BasicBlock<'ctx>,
+}
+
+/// The code generator's side of the iteration protocol that `foreach` is
+/// lowered through. `next` is emitted at the top of every iteration: it
+/// branches to `body` with the following element, or to `done` once there
+/// are no more.
+trait Iteration<'ctx> {
+    fn next(&self, codegen: &CodeGen<'ctx>, body: BasicBlock<'ctx>, done: BasicBlock<'ctx>) -> BasicValueEnum<'ctx>;
+}
+
+/// Arrays are iterated by index, counting `index` up from zero to `len`.
+struct ArrayIteration<'ctx> {
+    first: PointerValue<'ctx>,
+    len: inkwell::values::IntValue<'ctx>,
+    index: PointerValue<'ctx>,
+}
+
+impl<'ctx> Iteration<'ctx> for ArrayIteration<'ctx> {
+    fn next(&self, codegen: &CodeGen<'ctx>, body: BasicBlock<'ctx>, done: BasicBlock<'ctx>) -> BasicValueEnum<'ctx> {
+        use inkwell::IntPredicate;
+
+        let builder = &codegen.builder;
+        let index = builder.build_load(self.index, "foreach.index").into_int_value();
+        let more = builder.build_int_compare(IntPredicate::ULT, index, self.len, "foreach.more");
+        builder.build_conditional_branch(more, body, done);
+
+        builder.position_at_end(body);
+        let following = builder.build_int_add(index, index.get_type().const_int(1, false), "foreach.following");
+        builder.build_store(self.index, following);
+        let element = unsafe { builder.build_in_bounds_gep(self.first, &[index], "foreach.element") };
+        builder.build_load(element, "element")
+    }
 }
 
 impl<'ctx> CodeGen<'ctx> {
-    pub fn new(context: &'ctx Context, module_name: &str) -> Self {
+    pub fn new(context: &'ctx Context, module_name: &str, source_map: &'ctx SourceMap, literal_types: &'ctx HashMap<Span, Type>) -> Self {
         let builder = context.create_builder();
         let module = context.create_module(module_name);
 
@@ -30,129 +99,1116 @@ impl<'ctx> CodeGen<'ctx> {
             module,
             named_values: HashMap::new(),
             current_function: None,
+            current_return_type: None,
+            loops: Vec::new(),
+            signatures: HashMap::new(),
+            source_map,
+            literal_types,
+            bounds_checks: true,
+            index_ranges: HashMap::new(),
         }
     }
 
-    pub fn generate(&mut self, ast:&[ASTNode]) -> Result<(), String> {
+    /// Whether indexing checks the index against the array's length at
+    /// runtime, which it does by default.
+    pub fn with_bounds_checks(mut self, enabled: bool) -> Self {
+        self.bounds_checks = enabled;
+        self
+    }
+
+    pub fn generate(&mut self, ast:&[ASTNode]) -> Result<(), Box<Diagnostic>> {
+        // Declare every function first so calls can come before the callee.
+        for node in ast {
+            if let ASTNode::FunctionDeclaration { name, parameters, return_type, .. } = node {
+                self.declare_function(name, parameters, return_type)?;
+            }
+        }
+
         for node in ast {
             self.generate_node(node)?;
         }
 
-        Ok(())
+        // invalid IR is a bug here rather than in the program, so report it
+        // before anything is emitted from it
+        self.module.verify().map_err(|message| {
+            Diagnostic::error(codes::BACKEND, "generated invalid LLVM IR").with_note(message.to_string().trim_end()).into()
+        })
     }
 
-    fn generate_node(&mut self, node: &ASTNode) -> Result<Option<BasicValueEnum<'ctx>>, String> {
+    fn generate_node(&mut self, node: &ASTNode) -> Result<Option<BasicValueEnum<'ctx>>, Box<Diagnostic>> {
         match node {
             ASTNode::FunctionDeclaration { name, parameters, return_type, body } => {
                 self.generate_function_declaration(name, parameters, return_type, body)
             }
-            ASTNode::VariableDeclaration { is_const: _, name, type_annotation: _, initializer } => {
-                self.generate_variable_declaration(name, initializer)
+            ASTNode::VariableDeclaration { is_const: _, name, type_annotation, initializer } => {
+                self.generate_variable_declaration(name, type_annotation.as_ref(), initializer)
             }
             ASTNode::ReturnStatement { value } => {
                 self.generate_return(value)
             }
-            ASTNode::Expression { token } => {
-                Ok(Some(self.generate_literal(token)))
+            ASTNode::Assign { target, op, value } => {
+                self.generate_assign(target, op, value)
+            }
+            ASTNode::If { condition, then_branch, else_branch, .. } => {
+                self.generate_if(condition, then_branch, else_branch.as_deref(), None, false)?;
+                Ok(None)
+            }
+            ASTNode::Match { scrutinee, arms, .. } => {
+                self.generate_match(scrutinee, arms, None, false)?;
+                Ok(None)
             }
-            ASTNode::VariableExpression { name } => {
-                self.generate_variable_load(name)
+            ASTNode::While { condition, body, .. } => {
+                self.generate_while(condition, body)
             }
-            ASTNode::FunctionCallExpression { name, arguments } => {
-                self.generate_function_call(name, arguments)
+            ASTNode::For { variable, start, end, inclusive, body, .. } => {
+                self.generate_for(variable, start, end, *inclusive, body)
             }
-            _ => Err("error: unsupported AST node for codegen".to_string()),
+            ASTNode::ForEach { variable, collection, body, .. } => {
+                self.generate_foreach(variable, collection, body)
+            }
+            ASTNode::Break { condition, body, .. } => {
+                let target = self.loops.last().unwrap().break_block;
+                self.generate_loop_control(target, condition.as_deref(), body.as_deref())
+            }
+            ASTNode::Continue { condition, body, .. } => {
+                let target = self.loops.last().unwrap().continue_block;
+                self.generate_loop_control(target, condition.as_deref(), body.as_deref())
+            }
+            _ => Ok(self.generate_expression(node, None)?.map(|v| v.value)),
         }
     }
 
-    fn get_type(&self, type_str: &str) -> BasicTypeEnum<'ctx> {
-        match type_str {
-            "i32" => self.context.i32_type().into(),
-            "f64" => self.context.f64_type().into(),
-            _ => panic!("error: unsupported type {}", type_str),
+    /// Generates `node`, giving unsuffixed literals the type `hint` when they
+    /// can take it. Calls to `void` functions have no value.
+    fn generate_expression(&mut self, node: &ASTNode, hint: Option<&Type>) -> Result<Option<Value<'ctx>>, Box<Diagnostic>> {
+        match node {
+            ASTNode::Expression { token } => self.generate_literal(token, hint).map(Some),
+            ASTNode::VariableExpression { name } => self.generate_variable_load(name).map(Some),
+            ASTNode::FunctionCallExpression { name, arguments } => self.generate_function_call(name, arguments),
+            ASTNode::Binary { left, operator, right } => self.generate_binary(left, operator, right, hint).map(Some),
+            ASTNode::Unary { operator, operand } => self.generate_unary(operator, operand, hint).map(Some),
+            ASTNode::Cast { value, target_type } => self.generate_cast(value, target_type).map(Some),
+            ASTNode::Block { .. } => self.generate_block(node, hint),
+            ASTNode::ArrayLiteral { elements, .. } => self.generate_array_literal(elements, hint).map(Some),
+            ASTNode::If { condition, then_branch, else_branch, .. } => {
+                self.generate_if(condition, then_branch, else_branch.as_deref(), hint, true)
+            }
+            ASTNode::Match { scrutinee, arms, .. } => self.generate_match(scrutinee, arms, hint, true),
+            ASTNode::Index { .. } => {
+                let (pointer, ty) = self.generate_place(node)?;
+                let value = self.builder.build_load(pointer, "element");
+
+                Ok(Some(Value { value, ty }))
+            }
+            _ => Err(Diagnostic::error(codes::UNSUPPORTED, "unsupported AST node for codegen").with_span(node.span()).into()),
         }
     }
 
-    fn generate_literal(&self, token: &Token) -> BasicValueEnum<'ctx> {
-        match &token.token_type {
-            TokenType::IntLiteral(val) => self.context.i32_type().const_int(*val as u64, false).into(),
-            TokenType::FloatLiteral(val) => self.context.f64_type().const_float(*val).into(),
-            TokenType::CharLiteral(val) => self.context.i8_type().const_int(*val as u64, false).into(),
-            // theres something wrong about casting the char to a u64 when im trying to print that
-            // out
-            _ => panic!("error: unsupported literal type"),
+    /// Generates a block's statements with the variables it declares going
+    /// out of scope at the end, and returns its value if it has one.
+    fn generate_block(&mut self, block: &ASTNode, hint: Option<&Type>) -> Result<Option<Value<'ctx>>, Box<Diagnostic>> {
+        let ASTNode::Block { statements, value, .. } = block else {
+            unreachable!("only called for blocks");
+        };
+
+        let outer_scope = self.named_values.clone();
+        for stmt in statements {
+            if self.is_terminated() {
+                break;
+            }
+
+            self.generate_node(stmt)?;
+        }
+
+        let value = match value {
+            Some(value) if !self.is_terminated() => self.generate_expression(value, hint)?,
+            _ => None,
+        };
+        self.named_values = outer_scope;
+
+        Ok(value)
+    }
+
+    /// Generates an `if` with a block for each branch that meet in `if.end`.
+    /// As an expression, the branch values come together in a phi there.
+    fn generate_if(
+        &mut self,
+        condition: &ASTNode,
+        then_branch: &ASTNode,
+        else_branch: Option<&ASTNode>,
+        hint: Option<&Type>,
+        is_expression: bool,
+    ) -> Result<Option<Value<'ctx>>, Box<Diagnostic>> {
+        let function = self.current_function.unwrap();
+
+        let condition = self.generate_value(condition, Some(&Type::Bool))?.value.into_int_value();
+        let then_block = self.context.append_basic_block(function, "if.then");
+        let else_block = else_branch.map(|_| self.context.append_basic_block(function, "if.else"));
+        let end_block = self.context.append_basic_block(function, "if.end");
+        self.builder.build_conditional_branch(condition, then_block, else_block.unwrap_or(end_block));
+
+        let mut branches = vec![(then_block, then_branch)];
+        if let (Some(block), Some(branch)) = (else_block, else_branch) {
+            // an unsuffixed literal takes the type of the other branch, so generate that one first
+            if then_branch.is_untyped_literal() && !branch.is_untyped_literal() {
+                branches.insert(0, (block, branch));
+            } else {
+                branches.push((block, branch));
+            }
+        }
+
+        let mut hint = hint.cloned();
+        let mut incoming = Vec::new();
+        for (block, branch) in branches {
+            self.builder.position_at_end(block);
+            let value = match branch {
+                ASTNode::If { condition, then_branch, else_branch, .. } => {
+                    self.generate_if(condition, then_branch, else_branch.as_deref(), hint.as_ref(), is_expression)?
+                }
+                _ => self.generate_block(branch, hint.as_ref())?,
+            };
+
+            if !self.is_terminated() {
+                self.builder.build_unconditional_branch(end_block);
+                if let Some(value) = value {
+                    hint = Some(value.ty.clone());
+                    incoming.push((value, self.builder.get_insert_block().unwrap()));
+                }
+            }
+        }
+
+        self.builder.position_at_end(end_block);
+        if end_block.get_first_use().is_none() {
+            // every branch returned
+            self.builder.build_unreachable();
+            return Ok(None);
+        }
+
+        let Some((first, _)) = incoming.first().filter(|_| is_expression) else {
+            return Ok(None);
+        };
+
+        let ty = first.ty.clone();
+        let phi = self.builder.build_phi(first.value.get_type(), "if.value");
+        for (value, block) in &incoming {
+            phi.add_incoming(&[(&value.value, *block)]);
         }
+
+        Ok(Some(Value { value: phi.as_basic_value(), ty }))
     }
 
-    fn generate_function_declaration(&mut self, name: &Token, params: &[(Token, Token)],
-                                    return_type: &Token, body: &[ASTNode]) -> 
-                                    Result<Option<BasicValueEnum<'ctx>>, String> 
+    /// Generates a `match` with a block for each arm that meet in
+    /// `match.end`, like `generate_if`. Arms of only literals and catch-alls
+    /// without guards become a `switch`; anything else tests the arms in
+    /// order with a chain of comparisons. The semantic checker makes sure
+    /// some arm always matches, so `match.none` is unreachable.
+    fn generate_match(
+        &mut self,
+        scrutinee: &ASTNode,
+        arms: &[MatchArm],
+        hint: Option<&Type>,
+        is_expression: bool,
+    ) -> Result<Option<Value<'ctx>>, Box<Diagnostic>> {
+        let function = self.current_function.unwrap();
+
+        let scrutinee = self.generate_value(scrutinee, None)?;
+        let arm_blocks: Vec<_> = arms.iter().map(|_| self.context.append_basic_block(function, "match.arm")).collect();
+        let none_block = self.context.append_basic_block(function, "match.none");
+        let end_block = self.context.append_basic_block(function, "match.end");
+
+        // where each arm goes when its guard fails
+        let mut fallthrough = vec![none_block; arms.len()];
+        // other scrutinees, such as floats, can only meet catch-all patterns
+        let is_integral = scrutinee.ty.is_integer() || matches!(scrutinee.ty, Type::Char | Type::Bool);
+        let is_switch = is_integral && arms.iter().all(|arm| {
+            arm.guard.is_none() && matches!(arm.pattern, Pattern::Literal(_) | Pattern::Wildcard(_) | Pattern::Binding(_))
+        });
+
+        if is_switch {
+            let mut cases: Vec<(inkwell::values::IntValue<'ctx>, BasicBlock<'ctx>)> = Vec::new();
+            let mut default = none_block;
+            for (arm, block) in arms.iter().zip(&arm_blocks) {
+                let Pattern::Literal(literal) = &arm.pattern else {
+                    default = *block;
+                    break;
+                };
+
+                // a repeated value can never reach its later arm, and a switch cannot list it twice
+                let value = self.generate_value(literal, Some(&scrutinee.ty))?.value.into_int_value();
+                if !cases.iter().any(|(case, _)| case.get_zero_extended_constant() == value.get_zero_extended_constant()) {
+                    cases.push((value, *block));
+                }
+            }
+
+            self.builder.build_switch(scrutinee.value.into_int_value(), default, &cases);
+        } else {
+            for (i, (arm, block)) in arms.iter().zip(&arm_blocks).enumerate() {
+                let next = if i + 1 < arms.len() { self.context.append_basic_block(function, "match.test") } else { none_block };
+
+                match self.generate_pattern_test(&arm.pattern, &scrutinee)? {
+                    Some(matches) => self.builder.build_conditional_branch(matches, *block, next),
+                    None => self.builder.build_unconditional_branch(*block),
+                };
+
+                fallthrough[i] = next;
+                self.builder.position_at_end(next);
+            }
+        }
+
+        // an unsuffixed literal takes the type of the other arms, so generate those first
+        let mut order: Vec<usize> = (0..arms.len()).collect();
+        order.sort_by_key(|&i| arms[i].body.is_untyped_literal());
+
+        let mut hint = hint.cloned();
+        let mut incoming = Vec::new();
+        for i in order {
+            let arm = &arms[i];
+            self.builder.position_at_end(arm_blocks[i]);
+
+            let outer_scope = self.named_values.clone();
+            if let Pattern::Binding(name) = &arm.pattern {
+                let alloca = self.create_entry_block_alloca(name.lexeme, scrutinee.value.get_type());
+                self.builder.build_store(alloca, scrutinee.value);
+                self.named_values.insert(name.lexeme.to_string(), (alloca, scrutinee.ty.clone()));
+            }
+
+            if let Some(guard) = &arm.guard {
+                let guard = self.generate_value(guard, Some(&Type::Bool))?.value.into_int_value();
+                let body_block = self.context.append_basic_block(function, "match.body");
+                self.builder.build_conditional_branch(guard, body_block, fallthrough[i]);
+                self.builder.position_at_end(body_block);
+            }
+
+            let value = self.generate_expression(&arm.body, hint.as_ref())?;
+            self.named_values = outer_scope;
+
+            if !self.is_terminated() {
+                self.builder.build_unconditional_branch(end_block);
+                if let Some(value) = value {
+                    hint = Some(value.ty.clone());
+                    incoming.push((value, self.builder.get_insert_block().unwrap()));
+                }
+            }
+        }
+
+        self.builder.position_at_end(none_block);
+        self.builder.build_unreachable();
+
+        self.builder.position_at_end(end_block);
+        if end_block.get_first_use().is_none() {
+            // every arm returned
+            self.builder.build_unreachable();
+            return Ok(None);
+        }
+
+        let Some((first, _)) = incoming.first().filter(|_| is_expression) else {
+            return Ok(None);
+        };
+
+        let ty = first.ty.clone();
+        let phi = self.builder.build_phi(first.value.get_type(), "match.value");
+        for (value, block) in &incoming {
+            phi.add_incoming(&[(&value.value, *block)]);
+        }
+
+        Ok(Some(Value { value: phi.as_basic_value(), ty }))
+    }
+
+    /// Compares `scrutinee` against a pattern, or `None` for a pattern that
+    /// matches anything.
+    fn generate_pattern_test(&mut self, pattern: &Pattern, scrutinee: &Value<'ctx>) ->
+                                    Result<Option<inkwell::values::IntValue<'ctx>>, Box<Diagnostic>>
     {
-        let fn_name = name.lexeme;
-        
-        let param_types: Vec<inkwell::types::BasicMetadataTypeEnum> = params.iter()
-            .map(|(_, param_type)| self.get_type(param_type.lexeme).into())
-            .collect();
+        use inkwell::IntPredicate;
+
+        let matches = match pattern {
+            Pattern::Wildcard(_) | Pattern::Binding(_) => return Ok(None),
+            Pattern::Literal(literal) => {
+                let value = scrutinee.value.into_int_value();
+                let literal = self.generate_value(literal, Some(&scrutinee.ty))?.value.into_int_value();
+                self.builder.build_int_compare(IntPredicate::EQ, value, literal, "match.eq")
+            }
+            Pattern::Range { start, end } => {
+                let value = scrutinee.value.into_int_value();
+                let start = self.generate_value(start, Some(&scrutinee.ty))?.value.into_int_value();
+                let end = self.generate_value(end, Some(&scrutinee.ty))?.value.into_int_value();
+                let (ge, le) = if scrutinee.ty.is_signed() {
+                    (IntPredicate::SGE, IntPredicate::SLE)
+                } else {
+                    (IntPredicate::UGE, IntPredicate::ULE)
+                };
+
+                let above = self.builder.build_int_compare(ge, value, start, "match.above");
+                let below = self.builder.build_int_compare(le, value, end, "match.below");
+                self.builder.build_and(above, below, "match.range")
+            }
+        };
+
+        Ok(Some(matches))
+    }
+
+    fn generate_while(&mut self, condition: &ASTNode, body: &ASTNode) -> Result<Option<BasicValueEnum<'ctx>>, Box<Diagnostic>> {
+        let function = self.current_function.unwrap();
+        let cond_block = self.context.append_basic_block(function, "while.cond");
+        let body_block = self.context.append_basic_block(function, "while.body");
+        let end_block = self.context.append_basic_block(function, "while.end");
+
+        self.builder.build_unconditional_branch(cond_block);
+        self.builder.position_at_end(cond_block);
+        let condition = self.generate_value(condition, Some(&Type::Bool))?.value.into_int_value();
+        self.builder.build_conditional_branch(condition, body_block, end_block);
 
-        let function = if return_type.lexeme == "void" {
-            let fn_type = self.context.void_type().fn_type(&param_types, false);
-            self.module.add_function(fn_name, fn_type, None)
+        self.builder.position_at_end(body_block);
+        self.loops.push(LoopTargets { continue_block: cond_block, break_block: end_block });
+        self.generate_block(body, None)?;
+        self.loops.pop();
+        if !self.is_terminated() {
+            self.builder.build_unconditional_branch(cond_block);
+        }
+
+        self.builder.position_at_end(end_block);
+        Ok(None)
+    }
+
+    /// Counts the loop variable from `start` to `end`, up or down. The loop
+    /// runs the body and then stops once the variable reaches the last
+    /// value, which never steps past the bounds, so `0u8..=255` terminates.
+    /// The direction is chosen here when both bounds are constants, and by
+    /// comparing them at runtime otherwise.
+    fn generate_for(&mut self, variable: &Token, start: &ASTNode, end: &ASTNode, inclusive: bool, body: &ASTNode) ->
+                                    Result<Option<BasicValueEnum<'ctx>>, Box<Diagnostic>>
+    {
+        use inkwell::IntPredicate;
+
+        let function = self.current_function.unwrap();
+        let (start, end) = self.generate_operands(start, end, None)?;
+        let ty = start.ty.clone();
+        let (start, end) = (start.value.into_int_value(), end.value.into_int_value());
+        let int_type = start.get_type();
+        let one = int_type.const_int(1, false);
+
+        let body_block = self.context.append_basic_block(function, "for.body");
+        let step_block = self.context.append_basic_block(function, "for.step");
+        let next_block = self.context.append_basic_block(function, "for.next");
+        let end_block = self.context.append_basic_block(function, "for.end");
+
+        let constant_bounds = match ty.is_signed() {
+            true => start.get_sign_extended_constant().zip(end.get_sign_extended_constant()).map(|(s, e)| s <= e),
+            false => start.get_zero_extended_constant().zip(end.get_zero_extended_constant()).map(|(s, e)| s <= e),
+        };
+        let ascending = match constant_bounds {
+            Some(ascending) => self.context.bool_type().const_int(ascending as u64, false),
+            None => {
+                let predicate = if ty.is_signed() { IntPredicate::SLE } else { IntPredicate::ULE };
+                self.builder.build_int_compare(predicate, start, end, "for.ascending")
+            }
+        };
+
+        // `start..end` stops one short of `end`, and is empty when they are equal
+        let last = if inclusive {
+            end
+        } else {
+            let below = self.builder.build_int_sub(end, one, "for.below");
+            let above = self.builder.build_int_add(end, one, "for.above");
+            self.builder.build_select(ascending, below, above, "for.last").into_int_value()
+        };
+        let stride = self.builder
+            .build_select(ascending, one, int_type.const_all_ones(), "for.stride")
+            .into_int_value();
+
+        let alloca = self.create_entry_block_alloca(variable.lexeme, int_type);
+        self.builder.build_store(alloca, start);
+        if let (Some(first), Some(bound)) = (constant_value(start, &ty), constant_value(end, &ty)) {
+            let last = match (first <= bound, inclusive) {
+                (_, true) => bound,
+                (true, false) => bound - 1,
+                (false, false) => bound + 1,
+            };
+            self.index_ranges.insert(alloca, (first.min(last), first.max(last)));
+        }
+        if inclusive {
+            self.builder.build_unconditional_branch(body_block);
         } else {
-            let ret_type = self.get_type(return_type.lexeme);
-            let fn_type = ret_type.fn_type(&param_types, false);
-            self.module.add_function(fn_name, fn_type, None)
+            let is_empty = self.builder.build_int_compare(IntPredicate::EQ, start, end, "for.empty");
+            self.builder.build_conditional_branch(is_empty, end_block, body_block);
+        }
+
+        self.builder.position_at_end(body_block);
+        let outer_scope = self.named_values.clone();
+        self.named_values.insert(variable.lexeme.to_string(), (alloca, ty));
+        self.loops.push(LoopTargets { continue_block: step_block, break_block: end_block });
+        self.generate_block(body, None)?;
+        self.loops.pop();
+        self.named_values = outer_scope;
+        if !self.is_terminated() {
+            self.builder.build_unconditional_branch(step_block);
+        }
+
+        self.builder.position_at_end(step_block);
+        let current = self.builder.build_load(alloca, variable.lexeme).into_int_value();
+        let is_last = self.builder.build_int_compare(IntPredicate::EQ, current, last, "for.done");
+        self.builder.build_conditional_branch(is_last, end_block, next_block);
+
+        self.builder.position_at_end(next_block);
+        let next = self.builder.build_int_add(current, stride, "for.next");
+        self.builder.build_store(alloca, next);
+        self.builder.build_unconditional_branch(body_block);
+
+        self.builder.position_at_end(end_block);
+        Ok(None)
+    }
+
+    /// Starts iterating over `collection`, returning how to step through it
+    /// and the type of its elements. Arrays are the only collections so far.
+    fn generate_iteration(&mut self, collection: &ASTNode) ->
+                                    Result<(Box<dyn Iteration<'ctx> + 'ctx>, Type), Box<Diagnostic>>
+    {
+        let (first, len, element_type) = self.generate_array_parts(collection, None)?;
+        let usize_type = self.context.i64_type();
+        let index = self.create_entry_block_alloca("foreach.index", usize_type);
+        self.builder.build_store(index, usize_type.const_zero());
+
+        Ok((Box::new(ArrayIteration { first, len, index }), element_type))
+    }
+
+    /// Lowers `foreach` to a loop that asks the collection's iteration for
+    /// the next element until there are none left.
+    fn generate_foreach(&mut self, variable: &Token, collection: &ASTNode, body: &ASTNode) ->
+                                    Result<Option<BasicValueEnum<'ctx>>, Box<Diagnostic>>
+    {
+        let function = self.current_function.unwrap();
+        let (iteration, element_type) = self.generate_iteration(collection)?;
+
+        let next_block = self.context.append_basic_block(function, "foreach.next");
+        let body_block = self.context.append_basic_block(function, "foreach.body");
+        let end_block = self.context.append_basic_block(function, "foreach.end");
+
+        let element_alloca = self.create_entry_block_alloca(variable.lexeme, self.llvm_type(&element_type).unwrap());
+        self.builder.build_unconditional_branch(next_block);
+
+        self.builder.position_at_end(next_block);
+        let element = iteration.next(self, body_block, end_block);
+        self.builder.build_store(element_alloca, element);
+
+        let outer_scope = self.named_values.clone();
+        self.named_values.insert(variable.lexeme.to_string(), (element_alloca, element_type));
+        self.loops.push(LoopTargets { continue_block: next_block, break_block: end_block });
+        self.generate_block(body, None)?;
+        self.loops.pop();
+        self.named_values = outer_scope;
+        if !self.is_terminated() {
+            self.builder.build_unconditional_branch(next_block);
+        }
+
+        self.builder.position_at_end(end_block);
+        Ok(None)
+    }
+
+    fn generate_array_literal(&mut self, elements: &[ASTNode], hint: Option<&Type>) -> Result<Value<'ctx>, Box<Diagnostic>> {
+        let (mut element_type, const_elements) = match hint {
+            Some(Type::Array { element, const_elements, .. }) => (Some((**element).clone()), *const_elements),
+            _ => (None, false),
+        };
+
+        // unsuffixed literals take the type the type checker inferred from
+        // the other elements, so the elements can be generated in order
+        if element_type.is_none() {
+            element_type = elements.first().and_then(|first| self.literal_type(first)).cloned();
+        }
+        let mut values = Vec::with_capacity(elements.len());
+        for element in elements {
+            let value = self.generate_value(element, element_type.as_ref())?;
+            element_type.get_or_insert(value.ty.clone());
+            values.push(value.value);
+        }
+
+        let element_type = element_type.unwrap();
+        let ty = Type::Array { element: Box::new(element_type), len: Some(elements.len() as u64), const_elements };
+        let mut array = self.llvm_type(&ty).unwrap().into_array_type().get_undef();
+        for (i, value) in values.into_iter().enumerate() {
+            array = self.builder
+                .build_insert_value(array, value, i as u32, "array")
+                .unwrap()
+                .into_array_value();
+        }
+
+        Ok(Value { value: array.into(), ty })
+    }
+
+    /// Returns a pointer to the first element of the array `node` evaluates
+    /// to, its length as a `usize`, and the element type. Variables and
+    /// elements of other arrays are used in place; any other array is first
+    /// stored to a temporary, with `hint` typing the elements of a literal.
+    fn generate_array_parts(&mut self, node: &ASTNode, hint: Option<&Type>) ->
+                                    Result<(PointerValue<'ctx>, inkwell::values::IntValue<'ctx>, Type), Box<Diagnostic>>
+    {
+        let (pointer, ty) = match node {
+            ASTNode::VariableExpression { .. } | ASTNode::Index { .. } => self.generate_place(node)?,
+            _ => {
+                let value = self.generate_value(node, hint)?;
+                let temporary = self.create_entry_block_alloca("array.tmp", value.value.get_type());
+                self.builder.build_store(temporary, value.value);
+
+                (temporary, value.ty)
+            }
+        };
+
+        let usize_type = self.context.i64_type();
+        match ty {
+            Type::Array { element, len: Some(len), .. } => {
+                let zero = usize_type.const_zero();
+                let first = unsafe { self.builder.build_in_bounds_gep(pointer, &[zero, zero], "array.first") };
+
+                Ok((first, usize_type.const_int(len, false), *element))
+            }
+            Type::Array { element, len: None, .. } => {
+                let view = self.builder.build_load(pointer, "view").into_struct_value();
+                let first = self.builder.build_extract_value(view, 0, "view.first").unwrap().into_pointer_value();
+                let len = self.builder.build_extract_value(view, 1, "view.len").unwrap().into_int_value();
+
+                Ok((first, len, *element))
+            }
+            other => Err(Diagnostic::error(codes::UNSUPPORTED, format!("`{}` is not an array", other)).with_span(node.span()).into()),
+        }
+    }
+
+    /// Jumps to `target` for `break` and `continue`, running `body` first.
+    /// With a condition, the jump only happens when it holds.
+    fn generate_loop_control(&mut self, target: BasicBlock<'ctx>, condition: Option<&ASTNode>, body: Option<&ASTNode>) ->
+                                    Result<Option<BasicValueEnum<'ctx>>, Box<Diagnostic>>
+    {
+        let Some(condition) = condition else {
+            self.builder.build_unconditional_branch(target);
+            return Ok(None);
         };
 
+        let function = self.current_function.unwrap();
+        let condition = self.generate_value(condition, Some(&Type::Bool))?.value.into_int_value();
+        let then_block = self.context.append_basic_block(function, "jump.then");
+        let skip_block = self.context.append_basic_block(function, "jump.skip");
+        self.builder.build_conditional_branch(condition, then_block, skip_block);
+
+        self.builder.position_at_end(then_block);
+        if let Some(body) = body {
+            self.generate_block(body, None)?;
+        }
+        if !self.is_terminated() {
+            self.builder.build_unconditional_branch(target);
+        }
+
+        self.builder.position_at_end(skip_block);
+        Ok(None)
+    }
+
+    /// Whether the block being generated already ends in a terminator, such
+    /// as after a `return`, so nothing more can be added to it.
+    fn is_terminated(&self) -> bool {
+        self.builder.get_insert_block().and_then(|block| block.get_terminator()).is_some()
+    }
+
+    /// Like `generate_expression`, for expressions that must have a value.
+    fn generate_value(&mut self, node: &ASTNode, hint: Option<&Type>) -> Result<Value<'ctx>, Box<Diagnostic>> {
+        self.generate_expression(node, hint)?.ok_or_else(|| {
+            Diagnostic::error(codes::UNSUPPORTED, "expression has no value").with_span(node.span()).into()
+        })
+    }
+
+    /// Generates the operands of an operator that needs them to have the same
+    /// type, left to right. An unsuffixed literal on the left takes the type
+    /// the type checker inferred for it from the right.
+    fn generate_operands(&mut self, left: &ASTNode, right: &ASTNode, hint: Option<&Type>) -> Result<(Value<'ctx>, Value<'ctx>), Box<Diagnostic>> {
+        let left_hint = self.literal_type(left).or(hint);
+        let lhs = self.generate_value(left, left_hint)?;
+        let rhs = self.generate_value(right, Some(&lhs.ty))?;
+
+        Ok((lhs, rhs))
+    }
+
+    /// The type the type checker gave `node` if it is an unsuffixed literal.
+    fn literal_type(&self, node: &ASTNode) -> Option<&'ctx Type> {
+        self.literal_types.get(&node.span()).filter(|_| node.is_untyped_literal())
+    }
+
+    fn generate_binary(&mut self, left: &ASTNode, operator: &Token, right: &ASTNode, hint: Option<&Type>) -> Result<Value<'ctx>, Box<Diagnostic>> {
+        let (lhs, rhs) = match operator.token_type {
+            TokenType::DoubleAmpersand | TokenType::DoublePipe => return self.generate_logical(left, operator, right),
+            TokenType::DoubleLeftAngle | TokenType::DoubleRightAngle => {
+                let lhs = self.generate_value(left, hint.filter(|t| t.is_integer()))?;
+                let rhs = self.generate_value(right, None)?;
+
+                (lhs, rhs)
+            }
+            TokenType::DoubleEqual
+            | TokenType::ExclamEqual
+            | TokenType::LeftAngle
+            | TokenType::RightAngle
+            | TokenType::LessEqual
+            | TokenType::GreaterEqual => self.generate_operands(left, right, None)?,
+            _ => self.generate_operands(left, right, hint)?,
+        };
+
+        self.build_binary(operator, &operator.token_type, lhs, rhs)
+    }
+
+    /// Applies the binary operator `kind` to two generated operands, which
+    /// have the same type except for the amount of a shift. `operator` is
+    /// the token diagnostics point at.
+    fn build_binary(&mut self, operator: &Token, kind: &TokenType, lhs: Value<'ctx>, rhs: Value<'ctx>) -> Result<Value<'ctx>, Box<Diagnostic>> {
+        use inkwell::{FloatPredicate, IntPredicate};
+
+        let ty = lhs.ty.clone();
+        let unsupported = || {
+            Diagnostic::error(codes::UNSUPPORTED, format!("unsupported operator `{}` for `{}`", operator.lexeme, ty))
+                .with_span(operator.span)
+                .into()
+        };
+
+        if ty.is_float() {
+            let (l, r) = (lhs.value.into_float_value(), rhs.value.into_float_value());
+            let predicate = match kind {
+                TokenType::DoubleEqual => Some(FloatPredicate::OEQ),
+                TokenType::ExclamEqual => Some(FloatPredicate::UNE),
+                TokenType::LeftAngle => Some(FloatPredicate::OLT),
+                TokenType::RightAngle => Some(FloatPredicate::OGT),
+                TokenType::LessEqual => Some(FloatPredicate::OLE),
+                TokenType::GreaterEqual => Some(FloatPredicate::OGE),
+                _ => None,
+            };
+            if let Some(predicate) = predicate {
+                let value = self.builder.build_float_compare(predicate, l, r, "cmp");
+                return Ok(Value { value: value.into(), ty: Type::Bool });
+            }
+
+            let value = match kind {
+                TokenType::Plus => self.builder.build_float_add(l, r, "add"),
+                TokenType::Minus => self.builder.build_float_sub(l, r, "sub"),
+                TokenType::Star => self.builder.build_float_mul(l, r, "mul"),
+                TokenType::ForwardSlash => self.builder.build_float_div(l, r, "div"),
+                TokenType::Modulo => self.builder.build_float_rem(l, r, "rem"),
+                _ => return Err(unsupported()),
+            };
+
+            return Ok(Value { value: value.into(), ty });
+        }
+
+        let (l, mut r) = (lhs.value.into_int_value(), rhs.value.into_int_value());
+        if matches!(kind, TokenType::DoubleLeftAngle | TokenType::DoubleRightAngle) {
+            // the shift amount takes the type of the value being shifted
+            r = self.builder.build_int_cast_sign_flag(r, l.get_type(), false, "shift_amount");
+        }
+
+        let signed = ty.is_signed();
+        let predicate = match kind {
+            TokenType::DoubleEqual => Some(IntPredicate::EQ),
+            TokenType::ExclamEqual => Some(IntPredicate::NE),
+            TokenType::LeftAngle => Some(if signed { IntPredicate::SLT } else { IntPredicate::ULT }),
+            TokenType::RightAngle => Some(if signed { IntPredicate::SGT } else { IntPredicate::UGT }),
+            TokenType::LessEqual => Some(if signed { IntPredicate::SLE } else { IntPredicate::ULE }),
+            TokenType::GreaterEqual => Some(if signed { IntPredicate::SGE } else { IntPredicate::UGE }),
+            _ => None,
+        };
+        if let Some(predicate) = predicate {
+            let value = self.builder.build_int_compare(predicate, l, r, "cmp");
+            return Ok(Value { value: value.into(), ty: Type::Bool });
+        }
+
+        let value = match kind {
+            TokenType::Plus => self.builder.build_int_add(l, r, "add"),
+            TokenType::Minus => self.builder.build_int_sub(l, r, "sub"),
+            TokenType::Star => self.builder.build_int_mul(l, r, "mul"),
+            TokenType::ForwardSlash if signed => self.builder.build_int_signed_div(l, r, "div"),
+            TokenType::ForwardSlash => self.builder.build_int_unsigned_div(l, r, "div"),
+            TokenType::Modulo if signed => self.builder.build_int_signed_rem(l, r, "rem"),
+            TokenType::Modulo => self.builder.build_int_unsigned_rem(l, r, "rem"),
+            TokenType::Ampersand => self.builder.build_and(l, r, "and"),
+            TokenType::Pipe => self.builder.build_or(l, r, "or"),
+            TokenType::Carrot => self.builder.build_xor(l, r, "xor"),
+            TokenType::DoubleLeftAngle => self.builder.build_left_shift(l, r, "shl"),
+            TokenType::DoubleRightAngle => self.builder.build_right_shift(l, r, signed, "shr"),
+            _ => return Err(unsupported()),
+        };
+
+        Ok(Value { value: value.into(), ty })
+    }
+
+    /// `&&` and `||` only evaluate their right operand when the left one does
+    /// not already decide the result.
+    fn generate_logical(&mut self, left: &ASTNode, operator: &Token, right: &ASTNode) -> Result<Value<'ctx>, Box<Diagnostic>> {
+        let function = self.current_function.unwrap();
+        let is_and = operator.token_type == TokenType::DoubleAmpersand;
+
+        let lhs = self.generate_value(left, Some(&Type::Bool))?.value.into_int_value();
+        let lhs_block = self.builder.get_insert_block().unwrap();
+        let rhs_block = self.context.append_basic_block(function, "logic.rhs");
+        let end_block = self.context.append_basic_block(function, "logic.end");
+
+        if is_and {
+            self.builder.build_conditional_branch(lhs, rhs_block, end_block);
+        } else {
+            self.builder.build_conditional_branch(lhs, end_block, rhs_block);
+        }
+
+        self.builder.position_at_end(rhs_block);
+        let rhs = self.generate_value(right, Some(&Type::Bool))?.value;
+        let rhs_block = self.builder.get_insert_block().unwrap();
+        self.builder.build_unconditional_branch(end_block);
+
+        self.builder.position_at_end(end_block);
+        let short_circuit = self.context.bool_type().const_int(!is_and as u64, false);
+        let phi = self.builder.build_phi(self.context.bool_type(), "logic");
+        phi.add_incoming(&[(&short_circuit, lhs_block), (&rhs, rhs_block)]);
+
+        Ok(Value { value: phi.as_basic_value(), ty: Type::Bool })
+    }
+
+    fn generate_unary(&mut self, operator: &Token, operand: &ASTNode, hint: Option<&Type>) -> Result<Value<'ctx>, Box<Diagnostic>> {
+        let operand = self.generate_value(operand, hint)?;
+
+        let value = match (&operator.token_type, operand.value) {
+            (TokenType::ExclamationMark, BasicValueEnum::IntValue(v)) => self.builder.build_not(v, "not").into(),
+            (TokenType::Minus, BasicValueEnum::IntValue(v)) => self.builder.build_int_neg(v, "neg").into(),
+            (TokenType::Minus, BasicValueEnum::FloatValue(v)) => self.builder.build_float_neg(v, "neg").into(),
+            _ => {
+                return Err(Diagnostic::error(codes::UNSUPPORTED, format!("unsupported operator `{}` for `{}`", operator.lexeme, operand.ty))
+                    .with_span(operator.span).into());
+            }
+        };
+
+        Ok(Value { value, ty: operand.ty })
+    }
+
+    fn generate_cast(&mut self, value: &ASTNode, target_type: &TypeExpr) -> Result<Value<'ctx>, Box<Diagnostic>> {
+        let source = self.generate_value(value, None)?;
+        let llvm_target = self.get_type(target_type)?;
+        let target = Type::from_expr(target_type).unwrap();
+
+        let value = match (source.ty.is_float(), target.is_float()) {
+            (true, true) => self.builder
+                .build_float_cast(source.value.into_float_value(), llvm_target.into_float_type(), "cast")
+                .into(),
+            (true, false) if target.is_signed() => self.builder
+                .build_float_to_signed_int(source.value.into_float_value(), llvm_target.into_int_type(), "cast")
+                .into(),
+            (true, false) => self.builder
+                .build_float_to_unsigned_int(source.value.into_float_value(), llvm_target.into_int_type(), "cast")
+                .into(),
+            (false, true) if source.ty.is_signed() => self.builder
+                .build_signed_int_to_float(source.value.into_int_value(), llvm_target.into_float_type(), "cast")
+                .into(),
+            (false, true) => self.builder
+                .build_unsigned_int_to_float(source.value.into_int_value(), llvm_target.into_float_type(), "cast")
+                .into(),
+            (false, false) => self.builder
+                .build_int_cast_sign_flag(source.value.into_int_value(), llvm_target.into_int_type(), source.ty.is_signed(), "cast")
+                .into(),
+        };
+
+        Ok(Value { value, ty: target })
+    }
+
+    fn get_type(&self, type_expr: &TypeExpr) -> Result<BasicTypeEnum<'ctx>, Box<Diagnostic>> {
+        Type::from_expr(type_expr)
+            .ok()
+            .and_then(|ty| self.llvm_type(&ty))
+            .ok_or_else(|| {
+                Diagnostic::error(codes::UNSUPPORTED, "unsupported type").with_span(type_expr.span()).into()
+            })
+    }
+
+    fn llvm_type(&self, ty: &Type) -> Option<BasicTypeEnum<'ctx>> {
+        let llvm_type = match ty {
+            Type::I8 | Type::U8 | Type::Char => self.context.i8_type().into(),
+            Type::I16 | Type::U16 => self.context.i16_type().into(),
+            Type::I32 | Type::U32 => self.context.i32_type().into(),
+            Type::I64 | Type::U64 | Type::ISize | Type::USize => self.context.i64_type().into(),
+            Type::F32 => self.context.f32_type().into(),
+            Type::F64 => self.context.f64_type().into(),
+            Type::Bool => self.context.bool_type().into(),
+            Type::Array { element, len: Some(len), .. } => self.llvm_type(element)?.array_type(u32::try_from(*len).ok()?).into(),
+            // a pointer to the first element and the length
+            Type::Array { element, len: None, .. } => {
+                let pointer = self.llvm_type(element)?.ptr_type(inkwell::AddressSpace::default());
+                self.context.struct_type(&[pointer.into(), self.context.i64_type().into()], false).into()
+            }
+            Type::Str | Type::Void => return None,
+        };
+
+        Some(llvm_type)
+    }
+
+    fn generate_literal(&self, token: &Token, hint: Option<&Type>) -> Result<Value<'ctx>, Box<Diagnostic>> {
+        match &token.token_type {
+            TokenType::IntLiteral { value, suffix } => {
+                let ty = match (suffix, hint) {
+                    (Some(suffix), _) => Type::from(*suffix),
+                    (None, Some(hint)) if hint.is_integer() => hint.clone(),
+                    (None, _) => Type::I32,
+                };
+                let value = self.llvm_type(&ty).unwrap().into_int_type().const_int(*value as u64, false);
+
+                Ok(Value { value: value.into(), ty })
+            }
+            TokenType::FloatLiteral { value, suffix } => {
+                let ty = match (suffix, hint) {
+                    (Some(suffix), _) => Type::from(*suffix),
+                    (None, Some(hint)) if hint.is_float() => hint.clone(),
+                    (None, _) => Type::F64,
+                };
+                let value = self.llvm_type(&ty).unwrap().into_float_type().const_float(*value);
+
+                Ok(Value { value: value.into(), ty })
+            }
+            TokenType::BoolLiteral(val) => {
+                let value = self.context.bool_type().const_int(*val as u64, false);
+
+                Ok(Value { value: value.into(), ty: Type::Bool })
+            }
+            TokenType::CharLiteral(val) => {
+                // theres something wrong about casting the char to a u64 when im trying to print that
+                // out
+                let value = self.context.i8_type().const_int(*val as u64, false);
+
+                Ok(Value { value: value.into(), ty: Type::Char })
+            }
+            _ => Err(Diagnostic::error(codes::UNSUPPORTED, "unsupported literal type").with_span(token.span).into()),
+        }
+    }
+
+    fn declare_function(&mut self, name: &Token, params: &[(Token, TypeExpr)], return_type: &TypeExpr) -> Result<(), Box<Diagnostic>> {
+        let param_types = params.iter()
+            .map(|(_, param_type)| self.get_type(param_type).map(Into::into))
+            .collect::<Result<Vec<inkwell::types::BasicMetadataTypeEnum>, _>>()?;
+
+        let fn_type = if return_type.is_void() {
+            self.context.void_type().fn_type(&param_types, false)
+        } else {
+            self.get_type(return_type)?.fn_type(&param_types, false)
+        };
+        self.module.add_function(name.lexeme, fn_type, None);
+
+        let signature = (
+            params.iter().map(|(_, param_type)| Type::from_expr(param_type).unwrap()).collect(),
+            Type::from_expr(return_type).unwrap(),
+        );
+        self.signatures.insert(name.lexeme.to_string(), signature);
+
+        Ok(())
+    }
+
+    fn generate_function_declaration(&mut self, name: &Token, params: &[(Token, TypeExpr)],
+                                    return_type: &TypeExpr, body: &[ASTNode]) -> 
+                                    Result<Option<BasicValueEnum<'ctx>>, Box<Diagnostic>> 
+    {
+        let function = self.module.get_function(name.lexeme).unwrap();
+        let (param_types, ret_type) = self.signatures[name.lexeme].clone();
+
         let entry = self.context.append_basic_block(function, "entry");
 
         self.builder.position_at_end(entry);
         self.current_function = Some(function);
+        self.current_return_type = Some(ret_type);
         self.named_values.clear();
 
-        for (i, param) in function.get_param_iter().enumerate() {
-            let param_name = params[i].0.lexeme;
-            let alloca = self.create_entry_block_alloca(param_name, param.get_type());
+        for ((param, (param_name, _)), param_type) in function.get_param_iter().zip(params).zip(param_types) {
+            let alloca = self.create_entry_block_alloca(param_name.lexeme, param.get_type());
             self.builder.build_store(alloca, param);
-            self.named_values.insert(param_name.to_string(), alloca);
+            self.named_values.insert(param_name.lexeme.to_string(), (alloca, param_type));
         }
 
         for node in body {
+            if self.is_terminated() {
+                break;
+            }
+
             self.generate_node(node)?;
         }
 
-        if return_type.lexeme == "void" && self.builder.get_insert_block().and_then(|b| b.get_terminator()).is_none() {
+        if !self.is_terminated() {
+            if return_type.is_void() {
                 self.builder.build_return(None);
+            } else {
+                // the semantic checker made sure every path returns first
+                self.builder.build_unreachable();
+            }
         }
 
         Ok(Some(function.as_global_value().as_basic_value_enum()))
     }
     
-    fn generate_variable_declaration(&mut self, name: &Token, initializer: &ASTNode) -> 
-                                    Result<Option<BasicValueEnum<'ctx>>, String> 
+    fn generate_variable_declaration(&mut self, name: &Token, type_annotation: Option<&TypeExpr>, initializer: &ASTNode) -> 
+                                    Result<Option<BasicValueEnum<'ctx>>, Box<Diagnostic>> 
     {
         let var_name = name.lexeme;
-        let initial_value = self.generate_node(initializer)?.unwrap();
+        let annotated = type_annotation.and_then(|t| Type::from_expr(t).ok());
+        let initial_value = self.generate_value(initializer, annotated.as_ref())?;
         
-        let alloca = self.create_entry_block_alloca(var_name, initial_value.get_type());
-        self.builder.build_store(alloca, initial_value);
-        self.named_values.insert(var_name.to_string(), alloca);
+        let alloca = self.create_entry_block_alloca(var_name, initial_value.value.get_type());
+        self.builder.build_store(alloca, initial_value.value);
+        self.named_values.insert(var_name.to_string(), (alloca, annotated.unwrap_or(initial_value.ty)));
 
         Ok(None)
     }
 
-    fn generate_variable_load(&mut self, name: &Token) -> Result<Option<BasicValueEnum<'ctx>>, String> {
+    fn generate_variable_load(&mut self, name: &Token) -> Result<Value<'ctx>, Box<Diagnostic>> {
         let var_name = name.lexeme;
         match self.named_values.get(var_name) {
-            Some(var_ptr) => {
+            Some((var_ptr, ty)) => {
                 let loaded_val = self.builder.build_load(*var_ptr, var_name);
-                Ok(Some(loaded_val))
+                Ok(Value { value: loaded_val, ty: ty.clone() })
+            }
+            None => Err(Diagnostic::error(codes::UNKNOWN_VARIABLE, format!("unknown variable: {}", var_name))
+                .with_span(name.span).into()),
+        }
+    }
+
+    fn generate_assign(&mut self, target: &ASTNode, op: &Token, value: &ASTNode) -> Result<Option<BasicValueEnum<'ctx>>, Box<Diagnostic>> {
+        let (ptr, ty) = self.generate_place(target)?;
+
+        let new_value = match op.token_type.compound_operator() {
+            None => self.generate_value(value, Some(&ty))?,
+            Some(kind) => {
+                let current = Value { value: self.builder.build_load(ptr, "current"), ty: ty.clone() };
+                let hint = match kind {
+                    TokenType::DoubleLeftAngle | TokenType::DoubleRightAngle => None,
+                    _ => Some(&ty),
+                };
+                let rhs = self.generate_value(value, hint)?;
+
+                self.build_binary(op, &kind, current, rhs)?
+            }
+        };
+
+        self.builder.build_store(ptr, new_value.value);
+        Ok(None)
+    }
+
+    /// Returns the address an assignment to `target` stores through, which
+    /// for an index expression is its element.
+    fn generate_place(&mut self, target: &ASTNode) -> Result<(PointerValue<'ctx>, Type), Box<Diagnostic>> {
+        match target {
+            ASTNode::VariableExpression { name } => self.named_values.get(name.lexeme).cloned().ok_or_else(|| {
+                Diagnostic::error(codes::UNKNOWN_VARIABLE, format!("unknown variable: {}", name.lexeme))
+                    .with_span(name.span)
+                    .into()
+            }),
+            ASTNode::Index { target: array, index, .. } => {
+                let (first, len, element) = self.generate_array_parts(array, None)?;
+                let index = self.generate_index(index, len, target.span())?;
+                let pointer = unsafe { self.builder.build_in_bounds_gep(first, &[index], "element.ptr") };
+
+                Ok((pointer, element))
             }
-            None => Err(format!("Unknown variable: {}", var_name)),
+            _ => Err(Diagnostic::error(codes::UNSUPPORTED, "unsupported assignment target for codegen").with_span(target.span()).into()),
         }
     }
 
-    fn generate_return(&mut self, value: &ASTNode) -> Result<Option<BasicValueEnum<'ctx>>, String> {
-        let return_value = self.generate_node(value)?.unwrap();
+    /// Generates an array index as an `i64`, extending it by its signedness
+    /// so that a negative index stays negative. Unless the index is known to
+    /// be below `len`, it is checked against it, with `span` as the location
+    /// to report.
+    fn generate_index(&mut self, index: &ASTNode, len: inkwell::values::IntValue<'ctx>, span: Span) ->
+                                    Result<inkwell::values::IntValue<'ctx>, Box<Diagnostic>>
+    {
+        use inkwell::IntPredicate;
+
+        let index_value = self.generate_value(index, None)?;
+        let signed = index_value.ty.is_signed();
+        let range = match (constant_value(index_value.value.into_int_value(), &index_value.ty), index) {
+            (Some(value), _) => Some((value, value)),
+            (None, ASTNode::VariableExpression { name }) => {
+                self.named_values.get(name.lexeme).and_then(|(pointer, _)| self.index_ranges.get(pointer)).copied()
+            }
+            _ => None,
+        };
+        let usize_type = self.context.i64_type();
+        let index = self.builder.build_int_cast_sign_flag(index_value.value.into_int_value(), usize_type, signed, "index");
+
+        let in_range = match (range, len.get_zero_extended_constant()) {
+            (Some((lo, hi)), Some(len)) => lo >= 0 && hi < len as i128,
+            _ => false,
+        };
+        if !self.bounds_checks || in_range {
+            return Ok(index);
+        }
+
+        // a negative index is huge as an unsigned number, so one comparison covers both ends
+        let function = self.current_function.unwrap();
+        let in_bounds = self.builder.build_int_compare(IntPredicate::ULT, index, len, "in_bounds");
+        let fail_block = self.context.append_basic_block(function, "bounds.fail");
+        let ok_block = self.context.append_basic_block(function, "bounds.ok");
+        self.builder.build_conditional_branch(in_bounds, ok_block, fail_block);
+
+        self.builder.position_at_end(fail_block);
+        let file = self.source_map.file(span.file_id);
+        let location = file.location(span.lo);
+        let location = format!("{}:{}:{}", file.name(), location.line, location.column);
+        let location = self.builder.build_global_string_ptr(&location, "location").as_pointer_value();
+        let signed = self.context.bool_type().const_int(signed as u64, false);
+        let panic = self.get_bounds_panic();
+        self.builder.build_call(panic, &[index.into(), signed.into(), len.into(), location.into()], "");
+        self.builder.build_unreachable();
+
+        self.builder.position_at_end(ok_block);
+        Ok(index)
+    }
+
+    /// Defines `BOUNDS_PANIC` the first time it is needed. It takes the
+    /// index, whether the index is signed, the length and the location, and
+    /// writes its message to standard error.
+    fn get_bounds_panic(&mut self) -> FunctionValue<'ctx> {
+        use inkwell::attributes::{Attribute, AttributeLoc};
+        use inkwell::module::Linkage;
+
+        if let Some(function) = self.module.get_function(BOUNDS_PANIC) {
+            return function;
+        }
+
+        let i32_type = self.context.i32_type();
+        let i64_type = self.context.i64_type();
+        let i8_ptr_type = self.context.i8_type().ptr_type(inkwell::AddressSpace::default());
+        let dprintf = self.module.get_function("dprintf").unwrap_or_else(|| {
+            let dprintf_type = i32_type.fn_type(&[i32_type.into(), i8_ptr_type.into()], true);
+            self.module.add_function("dprintf", dprintf_type, None)
+        });
+        let exit = self.module.get_function("exit").unwrap_or_else(|| {
+            let exit_type = self.context.void_type().fn_type(&[i32_type.into()], false);
+            self.module.add_function("exit", exit_type, None)
+        });
+
+        let panic_type = self.context.void_type().fn_type(
+            &[i64_type.into(), self.context.bool_type().into(), i64_type.into(), i8_ptr_type.into()],
+            false,
+        );
+        let panic = self.module.add_function(BOUNDS_PANIC, panic_type, Some(Linkage::Internal));
+        for name in ["noreturn", "cold", "noinline"] {
+            let attribute = self.context.create_enum_attribute(Attribute::get_named_enum_kind_id(name), 0);
+            panic.add_attribute(AttributeLoc::Function, attribute);
+        }
+
+        // a builder of its own, so the function being generated keeps its position
+        let builder = self.context.create_builder();
+        builder.position_at_end(self.context.append_basic_block(panic, "entry"));
+        let [index, signed, len, location] = [0, 1, 2, 3].map(|i| panic.get_nth_param(i).unwrap());
+        let signed_format = builder.build_global_string_ptr("index %lld out of bounds for length %lld at %s\n", "bounds.signed");
+        let unsigned_format = builder.build_global_string_ptr("index %llu out of bounds for length %lld at %s\n", "bounds.unsigned");
+        let format = builder.build_select(
+            signed.into_int_value(),
+            signed_format.as_pointer_value(),
+            unsigned_format.as_pointer_value(),
+            "format",
+        );
+
+        let stderr = i32_type.const_int(2, false);
+        builder.build_call(dprintf, &[stderr.into(), format.into(), index.into(), len.into(), location.into()], "");
+        builder.build_call(exit, &[i32_type.const_int(BOUNDS_PANIC_EXIT_CODE, false).into()], "");
+        builder.build_unreachable();
+
+        panic
+    }
+
+    fn generate_return(&mut self, value: &ASTNode) -> Result<Option<BasicValueEnum<'ctx>>, Box<Diagnostic>> {
+        let hint = self.current_return_type.clone();
+        let return_value = self.generate_value(value, hint.as_ref())?.value;
         self.builder.build_return(Some(&return_value));
         Ok(None)
     }
@@ -169,24 +1225,52 @@ impl<'ctx> CodeGen<'ctx> {
         self.module.add_function("printf", printf_type, None)
     }
 
-    fn generate_function_call(&mut self, name: &Token, args: &[ASTNode]) -> Result<Option<BasicValueEnum<'ctx>>, String> {
+    fn generate_function_call(&mut self, name: &Token, args: &[ASTNode]) -> Result<Option<Value<'ctx>>, Box<Diagnostic>> {
         if name.lexeme == "println" {
-            return self.generate_println_call(args);
+            return self.generate_println_call(name, args);
         }
-        
-        Err(format!("Unknown function call: {}", name.lexeme))
+
+        let (Some(function), Some((param_types, return_type))) =
+            (self.module.get_function(name.lexeme), self.signatures.get(name.lexeme).cloned())
+        else {
+            return Err(Diagnostic::error(codes::UNKNOWN_FUNCTION, format!("unknown function call: {}", name.lexeme))
+                .with_span(name.span).into());
+        };
+
+        let mut call_args: Vec<BasicMetadataValueEnum<'ctx>> = Vec::new();
+        for (arg, param_type) in args.iter().zip(&param_types) {
+            let value = match param_type {
+                // an `anysize` parameter receives a view of the caller's array
+                Type::Array { len: None, .. } => {
+                    let (pointer, len, _) = self.generate_array_parts(arg, Some(param_type))?;
+                    let view_type = self.llvm_type(param_type).unwrap().into_struct_type();
+                    let view = self.builder.build_insert_value(view_type.get_undef(), pointer, 0, "view").unwrap();
+                    self.builder.build_insert_value(view, len, 1, "view").unwrap().as_basic_value_enum()
+                }
+                _ => self.generate_value(arg, Some(param_type))?.value,
+            };
+            call_args.push(value.into());
+        }
+
+        let call = self.builder.build_call(function, &call_args, name.lexeme);
+
+        Ok(call.try_as_basic_value().left().map(|value| Value { value, ty: return_type }))
     }
 
-    fn generate_println_call(&mut self, args: &[ASTNode]) -> Result<Option<BasicValueEnum<'ctx>>, String> {
+    fn generate_println_call(&mut self, name: &Token, args: &[ASTNode]) -> Result<Option<Value<'ctx>>, Box<Diagnostic>> {
         let printf = self.get_printf_declaration();
 
-        let format_str_node = args.first().ok_or("println requires a format string.")?;
+        let format_str_node = args.first().ok_or_else(|| {
+            Diagnostic::error(codes::INVALID_ARGUMENTS, "println requires a format string").with_span(name.span)
+        })?;
         let format_str_literal = match format_str_node {
             ASTNode::Expression { token } => match &token.token_type {
                 TokenType::StringLiteral(s) => s,
-                _ => return Err("First argument to println must be a string literal.".to_string()),
+                _ => return Err(Diagnostic::error(codes::INVALID_ARGUMENTS, "first argument to println must be a string literal")
+                    .with_span(token.span).into()),
             },
-            _ => return Err("Invalid first argument to println.".to_string()),
+            _ => return Err(Diagnostic::error(codes::INVALID_ARGUMENTS, "invalid first argument to println")
+                .with_span(name.span).into()),
         };
 
         
@@ -195,15 +1279,31 @@ impl<'ctx> CodeGen<'ctx> {
         // f32 and f64 map to %.2f for now
         // char maps to %c and prints the char value NOT ascii like %d
         // bool maps to %b
-        let c_format_str = format_str_literal.replace("{}", "%d") + "\n";
-        let format_str_ptr = self.builder
-            .build_global_string_ptr(&c_format_str, "format_str")
-            .as_pointer_value();
+        let mut c_format_str = Vec::with_capacity(format_str_literal.len() + 1);
+        let mut rest = &format_str_literal[..];
+        while let Some(at) = rest.windows(2).position(|pair| pair == b"{}") {
+            c_format_str.extend_from_slice(&rest[..at]);
+            c_format_str.extend_from_slice(b"%d");
+            rest = &rest[at + 2..];
+        }
+        c_format_str.extend_from_slice(rest);
+        c_format_str.push(b'\n');
+
+        // string literals are bytes and need not be UTF-8, so this cannot go
+        // through `build_global_string_ptr`
+        let format_str = self.context.const_string(&c_format_str, true);
+        let global = self.module.add_global(format_str.get_type(), None, "format_str");
+        global.set_linkage(inkwell::module::Linkage::Private);
+        global.set_constant(true);
+        global.set_unnamed_addr(true);
+        global.set_initializer(&format_str);
+        let zero = self.context.i32_type().const_zero();
+        let format_str_ptr = unsafe { global.as_pointer_value().const_in_bounds_gep(&[zero, zero]) };
 
         let mut printf_args: Vec<BasicMetadataValueEnum<'ctx>> = vec![format_str_ptr.into()];
 
         for arg_node in args.iter().skip(1) {
-            let arg_val = self.generate_node(arg_node)?.unwrap();
+            let arg_val = self.generate_value(arg_node, None)?.value;
             printf_args.push(arg_val.into());
         }
         
@@ -228,11 +1328,13 @@ impl<'ctx> CodeGen<'ctx> {
         self.module.print_to_string().to_string()
     }
 
-    pub fn write_to_object_file(&self, output_path: &std::path::Path) -> Result<(), String> {
-        Target::initialize_native(&InitializationConfig::default()).map_err(|e| e.to_string())?;
+    pub fn write_to_object_file(&self, output_path: &std::path::Path) -> Result<(), Box<Diagnostic>> {
+        let backend_error = |e: String| Box::new(Diagnostic::error(codes::BACKEND, e));
+
+        Target::initialize_native(&InitializationConfig::default()).map_err(backend_error)?;
 
         let triple = TargetMachine::get_default_triple();
-        let target = Target::from_triple(&triple).map_err(|e| e.to_string())?;
+        let target = Target::from_triple(&triple).map_err(|e| backend_error(e.to_string()))?;
         let target_machine = target
             .create_target_machine(
                 &triple,
@@ -242,7 +1344,7 @@ impl<'ctx> CodeGen<'ctx> {
                 inkwell::targets::RelocMode::PIC,
                 inkwell::targets::CodeModel::Default,
             )
-            .ok_or_else(|| "Could not create target machine".to_string())?;
+            .ok_or_else(|| backend_error("could not create target machine".to_string()))?;
 
         self.module.set_triple(&triple);
         self.module
@@ -254,6 +1356,257 @@ impl<'ctx> CodeGen<'ctx> {
                 inkwell::targets::FileType::Object,
                 output_path,
             )
-            .map_err(|e| e.to_string())
+            .map_err(|e| backend_error(e.to_string()))
+    }
+}
+
+/// The value of `value` if it is a constant, extended by the signedness of
+/// its type `ty`.
+fn constant_value(value: inkwell::values::IntValue, ty: &Type) -> Option<i128> {
+    if ty.is_signed() {
+        value.get_sign_extended_constant().map(i128::from)
+    } else {
+        value.get_zero_extended_constant().map(i128::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use inkwell::context::Context;
+
+    use diagnostics::SourceMap;
+    use lexer::Lexer;
+    use parser::parser::Parser;
+    use parser::semantic::SemanticChecker;
+    use parser::type_check::TypeChecker;
+
+    use crate::CodeGen;
+
+    /// Checks `source` as `hydrac` does and returns the module's IR.
+    fn compile(source: &str) -> String {
+        compile_with_bounds_checks(source, true)
+    }
+
+    fn compile_with_bounds_checks(source: &str, bounds_checks: bool) -> String {
+        let mut source_map = SourceMap::new();
+        let file_id = source_map.add_file("test.hydra", source);
+        let tokens = Lexer::with_file_id(source_map.source(file_id), file_id).tokenize().expect("Failed to tokenize");
+        let ast = Parser::new(tokens).parse().expect("Failed to parse");
+
+        let mut type_checker = TypeChecker::new();
+        type_checker.check(&ast).expect("Failed to type check");
+        SemanticChecker::new(type_checker.match_types()).check(&ast).expect("Failed semantic checks");
+
+        let context = Context::create();
+        let mut codegen = CodeGen::new(&context, "test", &source_map, type_checker.literal_types()).with_bounds_checks(bounds_checks);
+        codegen.generate(&ast).expect("Failed to generate");
+
+        codegen.ir_to_string()
+    }
+
+    /// The instructions of the basic block called `label`.
+    fn block<'i>(ir: &'i str, label: &str) -> &'i str {
+        let start = ir.find(&format!("\n{}:", label)).expect("no such block") + 1;
+        let body = &ir[start..];
+        let end = body.find("\n\n").unwrap_or(body.len());
+
+        body[..end].split_once('\n').map_or("", |(_, instructions)| instructions)
+    }
+
+    #[test]
+    fn test_if_expression_merges_through_phi() {
+        let ir = compile("fn pick(x: i32) -> i64 {
+            return if (x > 0) { 1 } else if (x < 0) { -1 } else { 0 };
+        }
+
+        fn main() -> void {
+            if (pick(2) == 1) { println(\"one\"); }
+        }");
+        assert!(ir.contains("%if.value = phi i64"));
+        assert!(ir.contains("br i1 %"));
+
+        // a statement `if` has no value to merge
+        let main = &ir[ir.find("define void @main").unwrap()..];
+        assert!(!main.contains("phi"));
+    }
+
+    #[test]
+    fn test_logical_operators_short_circuit() {
+        let ir = compile("fn check(a: bool, b: bool) -> bool {
+            return a && b || !a;
+        }
+
+        fn main() -> void {}");
+        // the right side only runs when the left does not decide the result
+        assert!(ir.contains("br i1 %a1, label %logic.rhs, label %logic.end"));
+        assert!(ir.contains("phi i1 [ false, %entry ], [ %b2, %logic.rhs ]"));
+        assert!(ir.contains("phi i1 [ true, %logic.end ], [ %not, %logic.rhs3 ]"));
+    }
+
+    #[test]
+    fn test_while_and_loop_control() {
+        let ir = compile("fn main() -> void {
+            let i = 0;
+            while (i < 10) {
+                i += 1;
+                continue if (i == 2);
+                break if (i > 5) { println(\"{}\", i); };
+            }
+        }");
+        assert!(block(&ir, "while.cond").contains("label %while.body, label %while.end"));
+        // `continue` goes back to the condition and `break` past the loop
+        assert_eq!(block(&ir, "jump.then").trim(), "br label %while.cond");
+        assert!(block(&ir, "jump.then6").ends_with("br label %while.end"));
+    }
+
+    #[test]
+    fn test_for_direction_is_only_compared_at_runtime_for_unknown_bounds() {
+        let ir = compile("fn count(n: i32) -> void {
+            for (i in 0..n) { println(\"{}\", i); }
+        }
+
+        fn main() -> void {
+            for (i in 5..=0) { println(\"{}\", i); }
+        }");
+        let (count, main) = ir.split_at(ir.find("define void @main").unwrap());
+        assert!(count.contains("%for.ascending = icmp sle i32 0, %"));
+        assert!(count.contains("select i1 %for.ascending"));
+        assert!(!main.contains("for.ascending"));
+        assert!(!main.contains("select"));
+    }
+
+    #[test]
+    fn test_match_lowers_to_switch_or_compare_chain() {
+        // only literal patterns and catch-alls become a switch
+        let ir = compile("fn main() -> void {
+            let x = 3;
+            let y = match (x) { 0 => 10, 1 => 20, _ => 30 };
+        }");
+        assert!(ir.contains("switch i32"));
+        assert!(ir.contains("%match.value = phi i32"));
+
+        let ir = compile("fn main() -> void {
+            let x = 3;
+            let y = match (x) { 0..=9 => 10, n if (n < 0) => 20, _ => 30 };
+        }");
+        assert!(!ir.contains("switch"));
+        assert!(ir.contains("match.test"));
+    }
+
+    #[test]
+    fn test_match_over_float_binding() {
+        let ir = compile("fn main() -> void {
+            let d = match (2.5) { f => f };
+            let e: f32 = match (1.5f32) { _ => 0.5 };
+            match (d) { x if (x > 1.0) => println(\"big\"), _ => {} }
+        }");
+        assert!(!ir.contains("switch"));
+        assert!(ir.contains("store double 2.500000e+00, double* %f"));
+        assert!(ir.contains("fcmp ogt double"));
+    }
+
+    #[test]
+    fn test_operands_are_evaluated_left_to_right() {
+        let ir = compile("fn f() -> bool { return true; }
+        fn g() -> i64 { return 2; }
+
+        fn main() -> void {
+            let x = if (f()) { 1 } else { 2 } + g();
+        }");
+        // the `if` takes its type from `g()` but still runs first
+        let main = &ir[ir.find("define void @main").unwrap()..];
+        assert!(main.find("call i1 @f()").unwrap() < main.find("call i64 @g()").unwrap());
+        assert!(main.contains("%if.value = phi i64"));
+    }
+
+    #[test]
+    fn test_array_literal_elements_are_generated_in_order() {
+        let ir = compile("fn f() -> bool { return true; }
+        fn g() -> i64 { return 2; }
+
+        fn main() -> void {
+            let a = { if (f()) { 1 } else { 2 }, g() };
+        }");
+        let main = &ir[ir.find("define void @main").unwrap()..];
+        assert!(main.find("call i1 @f()").unwrap() < main.find("call i64 @g()").unwrap());
+        assert!(main.contains("insertvalue [2 x i64] undef, i64 %if.value, 0"));
+    }
+
+    #[test]
+    fn test_anysize_argument_literal_takes_parameter_element_type() {
+        let ir = compile("fn sum(numbers: [i64, anysize]) -> i64 {
+            let total: i64 = 0;
+            foreach (n in numbers) { total += n; }
+            return total;
+        }
+
+        fn main() -> void {
+            println(\"{}\", sum({ 4, 5 }));
+        }");
+        assert!(ir.contains("store [2 x i64] [i64 4, i64 5]"));
+        assert!(ir.contains("insertvalue { i64*, i64 } undef, i64* %array.first, 0"));
+    }
+
+    #[test]
+    fn test_format_string_keeps_raw_bytes() {
+        let ir = compile("fn main() -> void {
+            println(\"caf\\xC3\\xA9 \\xFF {}\", 42);
+        }");
+        assert!(ir.contains("c\"caf\\C3\\A9 \\FF %d\\0A\\00\""));
+    }
+
+    const OUT_OF_BOUNDS: &str = "fn get(numbers: [i32, anysize], i: i64) -> i32 {
+    return numbers[i];
+}
+
+fn main() -> void {
+    let a: [i32, 5] = { 1, 2, 3, 4, 5 };
+    let k: u8 = 7;
+    println(\"{}\", a[k] + get(a, -2));
+}";
+
+    #[test]
+    fn test_bounds_checks_report_index_length_and_location() {
+        let ir = compile(OUT_OF_BOUNDS);
+        assert!(ir.contains("c\"index %lld out of bounds for length %lld at %s\\0A\\00\""));
+        assert!(ir.contains("c\"index %llu out of bounds for length %lld at %s\\0A\\00\""));
+        assert!(ir.contains("call void @exit(i32 101)"));
+
+        // the signed `i64` index is printed as negative, the `u8` one is not
+        let get = block(&ir, "bounds.fail");
+        assert!(get.contains("@hydra.panic_bounds(i64 %i1, i1 true, i64 %view.len, "));
+        assert!(ir.contains("c\"test.hydra:2:12\\00\""));
+        assert!(ir.contains(", i1 false, i64 5, "));
+        assert!(ir.contains("c\"test.hydra:8:19\\00\""));
+    }
+
+    #[test]
+    fn test_bounds_checks_are_left_out_when_the_index_is_in_range() {
+        let ir = compile("fn main() -> void {
+            let a: [i32, 5] = { 1, 2, 3, 4, 5 };
+            a[4] = a[0];
+            for (i in 0..5) { a[i] += 1; }
+            for (i in 4..=0) { a[i] += 1; }
+            for (i in 4..0) { a[i] += 1; }
+        }");
+        let main = &ir[ir.find("define void @main").unwrap()..];
+        assert!(main.contains("getelementptr inbounds"));
+        assert!(!main.contains("panic_bounds"));
+
+        // a range reaching past the end, or an index computed from the loop variable, is checked
+        let ir = compile("fn main() -> void {
+            let a: [i32, 5] = { 1, 2, 3, 4, 5 };
+            for (i in 0..=5) { a[i] += 1; }
+            for (i in 5..0) { a[i] += 1; }
+            for (i in 1..=5) { a[i - 1] += 1; }
+        }");
+        assert_eq!(ir.matches("call void @hydra.panic_bounds").count(), 3);
+    }
+
+    #[test]
+    fn test_unchecked_leaves_out_every_bounds_check() {
+        let ir = compile_with_bounds_checks(OUT_OF_BOUNDS, false);
+        assert!(!ir.contains("panic_bounds"));
+        assert!(!ir.contains("bounds.fail"));
     }
 }
@@ -0,0 +1,73 @@
+//! Stable diagnostic codes, grouped by the stage that reports them.
+
+// ---------------------------------------------------------------------------
+// Lexer (E00xx)
+// ---------------------------------------------------------------------------
+pub const UNEXPECTED_CHARACTER: &str = "E0001";
+pub const INVALID_ESCAPE: &str = "E0002";
+pub const UNTERMINATED_STRING: &str = "E0003";
+pub const UNTERMINATED_CHAR: &str = "E0004";
+pub const INVALID_CHAR_LITERAL: &str = "E0005";
+pub const INVALID_NUMBER: &str = "E0006";
+pub const INVALID_MULTILINE_STRING: &str = "E0007";
+pub const UNTERMINATED_BLOCK_COMMENT: &str = "E0008";
+
+// ---------------------------------------------------------------------------
+// Parser (E01xx)
+// ---------------------------------------------------------------------------
+pub const EXPECTED_TOKEN: &str = "E0100";
+pub const EXPECTED_EXPRESSION: &str = "E0101";
+pub const EXPECTED_TYPE: &str = "E0102";
+pub const MISSING_MAIN: &str = "E0103";
+pub const CHAINED_COMPARISON: &str = "E0104";
+pub const INVALID_ASSIGNMENT_TARGET: &str = "E0105";
+pub const EXPECTED_PATTERN: &str = "E0106";
+
+// ---------------------------------------------------------------------------
+// Type checker (E02xx)
+// ---------------------------------------------------------------------------
+pub const UNKNOWN_TYPE: &str = "E0200";
+pub const MISMATCHED_TYPES: &str = "E0201";
+pub const LITERAL_OUT_OF_RANGE: &str = "E0202";
+pub const UNDEFINED_VARIABLE: &str = "E0203";
+pub const UNDEFINED_FUNCTION: &str = "E0204";
+pub const ARGUMENT_COUNT: &str = "E0205";
+pub const INVALID_OPERAND: &str = "E0206";
+pub const INVALID_CAST: &str = "E0207";
+pub const ASSIGN_TO_CONST: &str = "E0208";
+pub const NOT_INDEXABLE: &str = "E0209";
+pub const UNKNOWN_FIELD: &str = "E0210";
+pub const IF_WITHOUT_ELSE: &str = "E0211";
+pub const INVALID_RANGE: &str = "E0212";
+pub const NOT_ITERABLE: &str = "E0213";
+pub const TYPE_ANNOTATIONS_NEEDED: &str = "E0214";
+pub const ANYSIZE_NOT_ALLOWED: &str = "E0215";
+pub const INVALID_INDEX: &str = "E0216";
+pub const ASSIGN_TO_CONST_ELEMENT: &str = "E0217";
+pub const CONST_ELEMENTS_DISCARDED: &str = "E0218";
+pub const ASSIGN_TO_ANYSIZE: &str = "E0219";
+pub const ARRAY_TOO_LARGE: &str = "E0220";
+
+// ---------------------------------------------------------------------------
+// Code generation (E03xx)
+// ---------------------------------------------------------------------------
+pub const UNSUPPORTED: &str = "E0300";
+pub const UNKNOWN_VARIABLE: &str = "E0301";
+pub const UNKNOWN_FUNCTION: &str = "E0302";
+pub const INVALID_ARGUMENTS: &str = "E0303";
+pub const BACKEND: &str = "E0304";
+
+// ---------------------------------------------------------------------------
+// Driver (E04xx)
+// ---------------------------------------------------------------------------
+pub const INVALID_INPUT: &str = "E0400";
+pub const IO: &str = "E0401";
+pub const LINKER: &str = "E0402";
+
+// ---------------------------------------------------------------------------
+// Semantic checks (E05xx)
+// ---------------------------------------------------------------------------
+pub const OUTSIDE_LOOP: &str = "E0500";
+pub const NON_EXHAUSTIVE_MATCH: &str = "E0501";
+pub const UNREACHABLE_PATTERN: &str = "E0502";
+pub const MISSING_RETURN: &str = "E0503";
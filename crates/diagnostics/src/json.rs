@@ -0,0 +1,93 @@
+use std::fmt::Write;
+
+use crate::diagnostic::Diagnostic;
+use crate::span::{SourceMap, Span};
+
+/// Serializes `diagnostic` as a single line of JSON, for editors and CI bots.
+///
+/// Byte offsets are 0-based and half-open; lines and columns are 1-based and
+/// columns count `char`s.
+pub fn to_json(diagnostic: &Diagnostic, source_map: &SourceMap) -> String {
+    let mut out = String::from("{");
+
+    let _ = write!(out, "\"severity\":{}", quote(&diagnostic.severity.to_string()));
+    let _ = write!(out, ",\"code\":{}", quote(diagnostic.code));
+    let _ = write!(out, ",\"message\":{}", quote(&diagnostic.message));
+
+    let file = diagnostic
+        .primary_span
+        .map(|span| quote(source_map.file(span.file_id).name()));
+    let _ = write!(out, ",\"file\":{}", file.as_deref().unwrap_or("null"));
+
+    let span = diagnostic.primary_span.map(|span| span_json(span, source_map));
+    let _ = write!(out, ",\"span\":{}", span.as_deref().unwrap_or("null"));
+
+    let labels: Vec<String> = diagnostic
+        .labels
+        .iter()
+        .map(|label| format!(
+            "{{\"message\":{},\"span\":{}}}",
+            quote(&label.message),
+            span_json(label.span, source_map),
+        ))
+        .collect();
+    let _ = write!(out, ",\"labels\":[{}]", labels.join(","));
+
+    let notes: Vec<String> = diagnostic.notes.iter().map(|note| quote(note)).collect();
+    let _ = write!(out, ",\"notes\":[{}]", notes.join(","));
+
+    let help = diagnostic.help.as_deref().map(quote);
+    let _ = write!(out, ",\"help\":{}", help.as_deref().unwrap_or("null"));
+
+    let suggestions: Vec<String> = diagnostic
+        .suggestions
+        .iter()
+        .map(|suggestion| format!(
+            "{{\"message\":{},\"replacement\":{},\"span\":{}}}",
+            quote(&suggestion.message),
+            quote(&suggestion.replacement),
+            span_json(suggestion.span, source_map),
+        ))
+        .collect();
+    let _ = write!(out, ",\"suggestions\":[{}]", suggestions.join(","));
+
+    out.push('}');
+    out
+}
+
+fn span_json(span: Span, source_map: &SourceMap) -> String {
+    let (lo, hi) = source_map.lookup(span);
+
+    format!(
+        "{{\"file\":{},\"byte_start\":{},\"byte_end\":{},\"line_start\":{},\"column_start\":{},\"line_end\":{},\"column_end\":{}}}",
+        quote(source_map.file(span.file_id).name()),
+        span.lo,
+        span.hi,
+        lo.line,
+        lo.column,
+        hi.line,
+        hi.column,
+    )
+}
+
+fn quote(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+
+    out.push('"');
+    out
+}
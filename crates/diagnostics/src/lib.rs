@@ -0,0 +1,113 @@
+pub mod codes;
+pub mod diagnostic;
+pub mod json;
+pub mod line_index;
+pub mod render;
+pub mod span;
+
+pub use diagnostic::{Diagnostic, Label, Severity, Suggestion};
+pub use line_index::{ColumnEncoding, LineIndex};
+pub use render::Renderer;
+pub use span::{FileId, Location, SourceFile, SourceMap, Span};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_snippet_with_labels() {
+        let mut source_map = SourceMap::new();
+        let file_id = source_map.add_file("main.hydra", "fn main() -> void {\n\tlet x: i32 = y;\n}\n");
+
+        let diagnostic = Diagnostic::error(codes::UNKNOWN_VARIABLE, "unknown variable: y")
+            .with_span(Span::new(file_id, 34, 35))
+            .with_label(Span::new(file_id, 25, 26), "declared here")
+            .with_help("declare 'y' before using it");
+
+        let rendered = Renderer::new(&source_map, false).render(&diagnostic);
+
+        assert_eq!(rendered, "\
+error[E0301]: unknown variable: y
+ --> main.hydra:2:15
+  |
+2 |     let x: i32 = y;
+  |                  ^
+  |         - declared here
+  |
+  = help: declare 'y' before using it
+");
+    }
+
+    #[test]
+    fn test_json_record() {
+        let mut source_map = SourceMap::new();
+        let file_id = source_map.add_file("dir\\main.hydra", "let x = 1\nlet y = 2;");
+
+        let diagnostic = Diagnostic::error(codes::EXPECTED_TOKEN, "expected \"';'\"")
+            .with_span(Span::new(file_id, 10, 13))
+            .with_suggestion(Span::new(file_id, 9, 9), ";", "add a semicolon");
+
+        assert_eq!(
+            json::to_json(&diagnostic, &source_map),
+            concat!(
+                r#"{"severity":"error","code":"E0100","message":"expected \"';'\"","file":"dir\\main.hydra","#,
+                r#""span":{"file":"dir\\main.hydra","byte_start":10,"byte_end":13,"line_start":2,"column_start":1,"line_end":2,"column_end":4},"#,
+                r#""labels":[],"notes":[],"help":null,"suggestions":[{"message":"add a semicolon","replacement":";","#,
+                r#""span":{"file":"dir\\main.hydra","byte_start":9,"byte_end":9,"line_start":1,"column_start":10,"line_end":1,"column_end":10}}]}"#,
+            ),
+        );
+    }
+
+    #[test]
+    fn test_line_index_column_encodings() {
+        let source = "fn main() {\n\tlet s = \"é😀\"; x\r\n}";
+        let index = LineIndex::new(source);
+        let x = source.find('x').unwrap();
+
+        let column = |encoding| index.location(source, x, encoding).column;
+        assert_eq!(index.line(x), 2);
+        assert_eq!(column(ColumnEncoding::Utf8), 20);
+        assert_eq!(column(ColumnEncoding::Utf16), 17);
+        assert_eq!(column(ColumnEncoding::Char), 16);
+        assert_eq!(column(ColumnEncoding::Visual { tab_width: 4 }), 19);
+
+        for encoding in [ColumnEncoding::Utf8, ColumnEncoding::Utf16, ColumnEncoding::Char, ColumnEncoding::Visual { tab_width: 8 }] {
+            for (offset, _) in source.char_indices().filter(|&(_, c)| c != '\n') {
+                let location = index.location(source, offset, encoding);
+                assert_eq!(index.offset(source, location, encoding), Some(offset), "{:?} at {}", encoding, offset);
+            }
+        }
+
+        // the middle of a surrogate pair and columns past the end of a line
+        let emoji = source.find('😀').unwrap();
+        assert_eq!(index.offset(source, Location { line: 2, column: 13 }, ColumnEncoding::Utf16), Some(emoji));
+        assert_eq!(index.offset(source, Location { line: 2, column: 99 }, ColumnEncoding::Utf16), Some(x + 1));
+        assert_eq!(index.offset(source, Location { line: 4, column: 1 }, ColumnEncoding::Utf16), None);
+    }
+
+    #[test]
+    fn test_tabs_advance_to_the_next_tab_stop() {
+        let source = "a\tb\n\tc\td;\n";
+        let index = LineIndex::new(source);
+        let visual = ColumnEncoding::Visual { tab_width: 4 };
+
+        assert_eq!(index.location(source, 2, visual).column, 5);
+        assert_eq!(index.location(source, source.find('d').unwrap(), visual).column, 9);
+        assert_eq!(index.offset(source, Location { line: 1, column: 4 }, visual), Some(1));
+
+        let mut source_map = SourceMap::new();
+        let file_id = source_map.add_file("main.hydra", source);
+        let d = source.find('d').unwrap();
+        let diagnostic = Diagnostic::error(codes::UNKNOWN_VARIABLE, "unknown variable: d").with_span(Span::new(file_id, d, d + 1));
+
+        let rendered = Renderer::new(&source_map, false).render(&diagnostic);
+
+        assert_eq!(rendered, "\
+error[E0301]: unknown variable: d
+ --> main.hydra:2:4
+  |
+2 |     c   d;
+  |         ^
+");
+    }
+}
@@ -0,0 +1,100 @@
+use crate::span::Location;
+
+/// The unit a column is counted in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnEncoding {
+    /// Bytes of UTF-8, the LSP `utf-8` position encoding.
+    Utf8,
+    /// UTF-16 code units, the default LSP position encoding.
+    Utf16,
+    /// Unicode scalar values (`char`s), as used by the lexer.
+    Char,
+    /// Columns on screen, with tab stops every `tab_width` columns.
+    Visual { tab_width: usize },
+}
+
+impl ColumnEncoding {
+    /// Returns the column following `c`, which starts at the 1-based `column`.
+    fn next_column(self, column: usize, c: char) -> usize {
+        match self {
+            ColumnEncoding::Utf8 => column + c.len_utf8(),
+            ColumnEncoding::Utf16 => column + c.len_utf16(),
+            ColumnEncoding::Char => column + 1,
+            // a tab moves to the next tab stop, so it can be narrower than `tab_width`
+            ColumnEncoding::Visual { tab_width } if c == '\t' && tab_width > 0 => {
+                column + tab_width - (column - 1) % tab_width
+            }
+            ColumnEncoding::Visual { .. } => column + 1,
+        }
+    }
+}
+
+/// The start offset of every line of a text, built once per file, for
+/// converting between byte offsets and 1-based line/column locations in any
+/// [`ColumnEncoding`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineIndex {
+    line_starts: Vec<usize>,
+    len: usize,
+}
+
+impl LineIndex {
+    pub fn new(text: &str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+
+        Self { line_starts, len: text.len() }
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Returns the 1-based line containing `offset`.
+    pub fn line(&self, offset: usize) -> usize {
+        match self.line_starts.binary_search(&offset.min(self.len)) {
+            Ok(line) => line + 1,
+            Err(next_line) => next_line,
+        }
+    }
+
+    /// Returns the byte range of the 1-based `line`, including its line
+    /// terminator.
+    pub fn line_range(&self, line: usize) -> Option<std::ops::Range<usize>> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        let end = self.line_starts.get(line).copied().unwrap_or(self.len);
+
+        Some(start..end)
+    }
+
+    /// Converts a byte offset into `text`, the text the index was built
+    /// from, into a location whose column counts in `encoding`.
+    pub fn location(&self, text: &str, offset: usize, encoding: ColumnEncoding) -> Location {
+        let offset = offset.min(self.len);
+        let line = self.line(offset);
+        let line_start = self.line_starts[line - 1];
+        let column = text[line_start..offset].chars().fold(1, |column, c| encoding.next_column(column, c));
+
+        Location { line, column }
+    }
+
+    /// Converts a location whose column counts in `encoding` back into a byte
+    /// offset into `text`. A column inside a character, such as between the
+    /// two halves of a UTF-16 surrogate pair, resolves to the start of that
+    /// character, and a column past the end of the line to the line's end.
+    pub fn offset(&self, text: &str, location: Location, encoding: ColumnEncoding) -> Option<usize> {
+        let range = self.line_range(location.line)?;
+        let line = text[range.clone()].trim_end_matches(['\n', '\r']);
+
+        let mut column = 1;
+        for (i, c) in line.char_indices() {
+            column = encoding.next_column(column, c);
+            if column > location.column {
+                return Some(range.start + i);
+            }
+        }
+
+        Some(range.start + line.len())
+    }
+}
@@ -0,0 +1,215 @@
+use std::fmt::Write;
+
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::line_index::ColumnEncoding;
+use crate::span::{SourceMap, Span};
+
+const TAB_WIDTH: usize = 4;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const BOLD_RED: &str = "\x1b[1;31m";
+const BOLD_YELLOW: &str = "\x1b[1;33m";
+const BOLD_GREEN: &str = "\x1b[1;32m";
+const BOLD_BLUE: &str = "\x1b[1;34m";
+const BOLD_CYAN: &str = "\x1b[1;36m";
+
+/// A span to underline on one source line, with 0-based visual columns.
+struct Annotation<'d> {
+    line: usize,
+    start: usize,
+    end: usize,
+    message: Option<&'d str>,
+    primary: bool,
+}
+
+/// Renders diagnostics the way rustc does: a header, the location, the
+/// offending source lines with underlined spans, then notes and help.
+pub struct Renderer<'a> {
+    source_map: &'a SourceMap,
+    color: bool,
+}
+
+impl<'a> Renderer<'a> {
+    pub fn new(source_map: &'a SourceMap, color: bool) -> Self {
+        Self { source_map, color }
+    }
+
+    pub fn render(&self, diagnostic: &Diagnostic) -> String {
+        let mut out = String::new();
+        let severity_style = match diagnostic.severity {
+            Severity::Error => BOLD_RED,
+            Severity::Warning => BOLD_YELLOW,
+            Severity::Note => BOLD_GREEN,
+        };
+
+        let _ = writeln!(
+            out,
+            "{}{}[{}]{}{}: {}{}",
+            self.style(severity_style),
+            diagnostic.severity,
+            diagnostic.code,
+            self.style(RESET),
+            self.style(BOLD),
+            diagnostic.message,
+            self.style(RESET),
+        );
+
+        let mut annotations = Vec::new();
+        if let Some(span) = diagnostic.primary_span {
+            annotations.push(self.annotation(span, None, true));
+        }
+        for label in &diagnostic.labels {
+            if Some(label.span.file_id) == diagnostic.primary_span.map(|s| s.file_id) {
+                annotations.push(self.annotation(label.span, Some(&label.message), false));
+            }
+        }
+
+        let gutter_width = annotations
+            .iter()
+            .map(|a| a.line.to_string().len())
+            .max()
+            .unwrap_or(0);
+        let gutter = " ".repeat(gutter_width);
+
+        if let Some(span) = diagnostic.primary_span {
+            let file = self.source_map.file(span.file_id);
+            let (location, _) = self.source_map.lookup(span);
+
+            let _ = writeln!(
+                out,
+                "{}{}-->{} {}:{}:{}",
+                gutter,
+                self.style(BOLD_BLUE),
+                self.style(RESET),
+                file.name(),
+                location.line,
+                location.column,
+            );
+            self.write_gutter(&mut out, &gutter, None);
+
+            annotations.sort_by_key(|a| (a.line, !a.primary, a.start));
+            let mut lines: Vec<usize> = annotations.iter().map(|a| a.line).collect();
+            lines.dedup();
+
+            let mut previous_line = None;
+            for line in lines {
+                if previous_line.is_some_and(|p| line > p + 1) {
+                    let _ = writeln!(out, "{}...{}", self.style(BOLD_BLUE), self.style(RESET));
+                }
+                previous_line = Some(line);
+
+                let text = expand_tabs(file.line_text(line).unwrap_or(""));
+                self.write_gutter(&mut out, &format!("{:>width$}", line, width = gutter_width), Some(&text));
+
+                for annotation in annotations.iter().filter(|a| a.line == line) {
+                    let (marker, style) = if annotation.primary {
+                        ('^', severity_style)
+                    } else {
+                        ('-', BOLD_BLUE)
+                    };
+                    let width = annotation.end.saturating_sub(annotation.start).max(1);
+                    let mut underline = format!(
+                        "{}{}{}",
+                        " ".repeat(annotation.start),
+                        self.style(style),
+                        marker.to_string().repeat(width),
+                    );
+                    if let Some(message) = annotation.message {
+                        let _ = write!(underline, " {}", message);
+                    }
+                    underline.push_str(self.style(RESET));
+
+                    self.write_gutter(&mut out, &gutter, Some(&underline));
+                }
+            }
+
+            if !diagnostic.notes.is_empty() || diagnostic.help.is_some() || !diagnostic.suggestions.is_empty() {
+                self.write_gutter(&mut out, &gutter, None);
+            }
+        }
+
+        for note in &diagnostic.notes {
+            self.write_footer(&mut out, &gutter, "note", note);
+        }
+        if let Some(help) = &diagnostic.help {
+            self.write_footer(&mut out, &gutter, "help", help);
+        }
+        for suggestion in &diagnostic.suggestions {
+            let help = format!("{}: `{}`", suggestion.message, suggestion.replacement);
+            self.write_footer(&mut out, &gutter, "help", &help);
+        }
+
+        out
+    }
+
+    /// Converts `span` into an annotation on its first line; spans that run
+    /// across lines are underlined to the end of that line.
+    fn annotation<'d>(&self, span: Span, message: Option<&'d str>, primary: bool) -> Annotation<'d> {
+        let file = self.source_map.file(span.file_id);
+        let visual = ColumnEncoding::Visual { tab_width: TAB_WIDTH };
+        let lo = file.location_in(span.lo, visual);
+        let hi = file.location_in(span.hi, visual);
+
+        let end = if hi.line == lo.line {
+            hi.column - 1
+        } else {
+            // underline to the end of the first line
+            let line_start = file.line_index().line_range(lo.line).map_or(0, |r| r.start);
+            let line_len = file.line_text(lo.line).map_or(0, str::len);
+            file.location_in(line_start + line_len, visual).column - 1
+        };
+
+        Annotation { line: lo.line, start: lo.column - 1, end, message, primary }
+    }
+
+    fn write_gutter(&self, out: &mut String, gutter: &str, text: Option<&str>) {
+        let _ = write!(out, "{}{} |{}", self.style(BOLD_BLUE), gutter, self.style(RESET));
+        match text {
+            Some(text) if !text.is_empty() => {
+                let _ = writeln!(out, " {}", text);
+            }
+            _ => out.push('\n'),
+        }
+    }
+
+    fn write_footer(&self, out: &mut String, gutter: &str, kind: &str, message: &str) {
+        let style = if kind == "help" { BOLD_CYAN } else { BOLD };
+
+        let _ = writeln!(
+            out,
+            "{}{} ={} {}{}:{} {}",
+            gutter,
+            self.style(BOLD_BLUE),
+            self.style(RESET),
+            self.style(style),
+            kind,
+            self.style(RESET),
+            message,
+        );
+    }
+
+    fn style(&self, code: &'static str) -> &'static str {
+        if self.color { code } else { "" }
+    }
+}
+
+/// Replaces each tab with spaces up to the next tab stop, keeping the text
+/// in line with the `ColumnEncoding::Visual` columns of its annotations.
+fn expand_tabs(text: &str) -> String {
+    let mut expanded = String::with_capacity(text.len());
+    let mut column = 0;
+
+    for c in text.chars() {
+        if c == '\t' {
+            let spaces = TAB_WIDTH - column % TAB_WIDTH;
+            expanded.push_str(&" ".repeat(spaces));
+            column += spaces;
+        } else {
+            expanded.push(c);
+            column += 1;
+        }
+    }
+
+    expanded
+}
@@ -0,0 +1,134 @@
+use crate::line_index::{ColumnEncoding, LineIndex};
+
+/// Identifies a file registered in a [`SourceMap`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct FileId(pub u32);
+
+/// A half-open range of byte offsets `lo..hi` into a single source file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Span {
+    pub file_id: FileId,
+    pub lo: usize,
+    pub hi: usize,
+}
+
+impl Span {
+    pub fn new(file_id: FileId, lo: usize, hi: usize) -> Self {
+        Self { file_id, lo, hi }
+    }
+
+    pub fn len(&self) -> usize {
+        self.hi - self.lo
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lo == self.hi
+    }
+
+    /// Returns the smallest span covering both `self` and `other`.
+    pub fn to(&self, other: Span) -> Span {
+        Span {
+            file_id: self.file_id,
+            lo: self.lo.min(other.lo),
+            hi: self.hi.max(other.hi),
+        }
+    }
+}
+
+/// A 1-based line and column pair. Columns count `char`s unless a
+/// [`ColumnEncoding`] says otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+pub struct SourceFile {
+    name: String,
+    source: String,
+    line_index: LineIndex,
+}
+
+impl SourceFile {
+    fn new(name: String, source: String) -> Self {
+        let line_index = LineIndex::new(&source);
+
+        Self { name, source, line_index }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_index.line_count()
+    }
+
+    pub fn line_index(&self) -> &LineIndex {
+        &self.line_index
+    }
+
+    pub fn location(&self, offset: usize) -> Location {
+        self.location_in(offset, ColumnEncoding::Char)
+    }
+
+    /// Returns the location of `offset` with the column counted in `encoding`.
+    pub fn location_in(&self, offset: usize, encoding: ColumnEncoding) -> Location {
+        self.line_index.location(&self.source, offset, encoding)
+    }
+
+    /// Returns the byte offset of `location`, whose column counts in `encoding`.
+    pub fn offset(&self, location: Location, encoding: ColumnEncoding) -> Option<usize> {
+        self.line_index.offset(&self.source, location, encoding)
+    }
+
+    /// Returns the text of the 1-based `line`, without its line terminator.
+    pub fn line_text(&self, line: usize) -> Option<&str> {
+        let range = self.line_index.line_range(line)?;
+
+        Some(self.source[range].trim_end_matches(['\n', '\r']))
+    }
+}
+
+/// Owns the source text of every file in a compilation and maps spans back
+/// to human readable locations.
+#[derive(Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_file(&mut self, name: impl Into<String>, source: impl Into<String>) -> FileId {
+        let id = FileId(self.files.len() as u32);
+        self.files.push(SourceFile::new(name.into(), source.into()));
+
+        id
+    }
+
+    pub fn file(&self, id: FileId) -> &SourceFile {
+        &self.files[id.0 as usize]
+    }
+
+    pub fn source(&self, id: FileId) -> &str {
+        self.file(id).source()
+    }
+
+    /// Returns the locations of the start and end of `span`.
+    pub fn lookup(&self, span: Span) -> (Location, Location) {
+        let file = self.file(span.file_id);
+
+        (file.location(span.lo), file.location(span.hi))
+    }
+
+    pub fn snippet(&self, span: Span) -> &str {
+        &self.source(span.file_id)[span.lo..span.hi]
+    }
+}
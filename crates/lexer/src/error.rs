@@ -0,0 +1,42 @@
+use diagnostics::{codes, Diagnostic, Span};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LexErrorKind {
+    UnexpectedCharacter,
+    InvalidEscape,
+    UnterminatedString,
+    UnterminatedChar,
+    InvalidCharLiteral,
+    InvalidNumber,
+    InvalidMultilineString,
+    UnterminatedBlockComment,
+}
+
+impl LexErrorKind {
+    pub fn code(&self) -> &'static str {
+        match self {
+            LexErrorKind::UnexpectedCharacter => codes::UNEXPECTED_CHARACTER,
+            LexErrorKind::InvalidEscape => codes::INVALID_ESCAPE,
+            LexErrorKind::UnterminatedString => codes::UNTERMINATED_STRING,
+            LexErrorKind::UnterminatedChar => codes::UNTERMINATED_CHAR,
+            LexErrorKind::InvalidCharLiteral => codes::INVALID_CHAR_LITERAL,
+            LexErrorKind::InvalidNumber => codes::INVALID_NUMBER,
+            LexErrorKind::InvalidMultilineString => codes::INVALID_MULTILINE_STRING,
+            LexErrorKind::UnterminatedBlockComment => codes::UNTERMINATED_BLOCK_COMMENT,
+        }
+    }
+}
+
+/// A lexical error, recorded by the lexer before it resumes scanning.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LexDiagnostic {
+    pub span: Span,
+    pub kind: LexErrorKind,
+    pub message: String,
+}
+
+impl From<LexDiagnostic> for Diagnostic {
+    fn from(diagnostic: LexDiagnostic) -> Self {
+        Diagnostic::error(diagnostic.kind.code(), diagnostic.message).with_span(diagnostic.span)
+    }
+}
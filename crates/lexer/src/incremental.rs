@@ -0,0 +1,188 @@
+use std::ops::Range;
+
+use diagnostics::{FileId, Span};
+
+use super::error::LexDiagnostic;
+use super::lexer::Lexer;
+use super::token::{Token, TokenType};
+
+/// How far past the end of a token the lexer may have looked while scanning
+/// it, in bytes: two characters of `peek`/`peek_next`.
+const MAX_LOOKAHEAD: usize = 8;
+
+/// Replaces the bytes `range` of a text with `replacement`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    pub range: Range<usize>,
+    pub replacement: String,
+}
+
+impl TextEdit {
+    pub fn new(range: Range<usize>, replacement: impl Into<String>) -> Self {
+        Self { range, replacement: replacement.into() }
+    }
+
+    /// The text after the edit.
+    pub fn apply(&self, text: &str) -> String {
+        let mut edited = String::with_capacity(text.len() + self.replacement.len() - self.range.len());
+        edited.push_str(&text[..self.range.start]);
+        edited.push_str(&self.replacement);
+        edited.push_str(&text[self.range.end..]);
+
+        edited
+    }
+
+    fn offset(&self) -> isize {
+        self.replacement.len() as isize - self.range.len() as isize
+    }
+}
+
+/// Moves tokens from before an edit onto the edited text.
+#[derive(Debug, Clone, Copy)]
+pub struct TokenShift<'b> {
+    text: &'b str,
+    edit_end: usize,
+    offset: isize,
+    sync_line: usize,
+    lines: isize,
+    columns: isize,
+}
+
+impl<'b> TokenShift<'b> {
+    /// The token as it appears in the edited text. Only tokens that
+    /// [`relex`] reported as unchanged may be passed in.
+    pub fn apply(&self, token: &Token<'_>) -> Token<'b> {
+        if token.span.lo < self.edit_end {
+            return self.with_span(token, token.span, token.line, token.column);
+        }
+
+        let column = if token.line == self.sync_line {
+            token.column.saturating_add_signed(self.columns)
+        } else {
+            token.column
+        };
+
+        self.with_span(token, self.shift_span(token.span), token.line.saturating_add_signed(self.lines), column)
+    }
+
+    fn with_span(&self, token: &Token<'_>, span: Span, line: usize, column: usize) -> Token<'b> {
+        Token {
+            token_type: token.token_type.clone(),
+            lexeme: &self.text[span.lo..span.hi],
+            span,
+            line,
+            column,
+        }
+    }
+
+    fn shift_span(&self, span: Span) -> Span {
+        Span::new(span.file_id, span.lo.saturating_add_signed(self.offset), span.hi.saturating_add_signed(self.offset))
+    }
+}
+
+/// The result of [`relex`].
+#[derive(Debug, Clone)]
+pub struct Relexed<'b> {
+    pub tokens: Vec<Token<'b>>,
+    pub diagnostics: Vec<LexDiagnostic>,
+    /// The number of leading tokens that are unchanged from the previous
+    /// stream, apart from pointing into the new text.
+    pub unchanged_prefix: usize,
+    /// The previous stream's tokens from `.0` on reappear, shifted, in the
+    /// new stream from `.1` on.
+    pub unchanged_suffix: (usize, usize),
+    pub shift: TokenShift<'b>,
+}
+
+/// Updates the token stream and lexical errors of a text after `edit` was
+/// applied to it, producing `new_text`. Only the tokens around the edit are
+/// scanned again; the result is exactly what lexing `new_text` from scratch
+/// with [`Lexer::tokenize_recovering`] produces.
+pub fn relex<'b>(
+    tokens: &[Token<'_>],
+    diagnostics: &[LexDiagnostic],
+    edit: &TextEdit,
+    new_text: &'b str,
+) -> Relexed<'b> {
+    let file_id = tokens.last().map_or(FileId::default(), |t| t.span.file_id);
+
+    // Tokens can look ahead to the end of their line, so everything from the
+    // first token that reaches the edited line on is scanned again, starting
+    // one token earlier because that is the last position whose line and
+    // column are known.
+    let line_start = new_text[..edit.range.start].rfind('\n').map_or(0, |i| i + 1);
+    let affected = tokens
+        .iter()
+        .position(|t| t.span.hi + MAX_LOOKAHEAD >= line_start)
+        .unwrap_or(tokens.len());
+    let restart = affected.saturating_sub(1);
+
+    let mut lexer = match tokens.get(restart).filter(|_| affected > 0) {
+        Some(token) => Lexer::resume(new_text, file_id, token.span.lo, token.line, token.column),
+        None => Lexer::with_file_id(new_text, file_id),
+    };
+    let restart_offset = tokens.get(restart).filter(|_| affected > 0).map_or(0, |t| t.span.lo);
+
+    let mut shift = TokenShift {
+        text: new_text,
+        edit_end: edit.range.end,
+        offset: edit.offset(),
+        sync_line: 0,
+        lines: 0,
+        columns: 0,
+    };
+    let mut new_tokens: Vec<_> = tokens[..restart].iter().map(|t| shift.apply(t)).collect();
+    let mut new_diagnostics: Vec<_> = diagnostics.iter().filter(|d| d.span.lo < restart_offset).cloned().collect();
+
+    // Scan until a token starts where an old token after the edit starts,
+    // from which point on the old stream is valid again. `EOF` always
+    // resynchronizes, as the old `EOF` sits at the end of the edit or later.
+    let mut old = affected;
+    let (token, sync) = loop {
+        let token = lexer.next_token();
+        let lo = token.span.lo as isize;
+
+        while old < tokens.len()
+            && (tokens[old].span.lo < edit.range.end || tokens[old].span.lo as isize + shift.offset < lo)
+        {
+            old += 1;
+        }
+
+        if let Some(sync) = tokens.get(old).filter(|t| t.span.lo as isize + shift.offset == lo) {
+            break (token, Some(sync));
+        }
+        if token.token_type == TokenType::EOF {
+            break (token, None);
+        }
+
+        new_tokens.push(token);
+    };
+
+    // errors are reported in source order, so the new ones are those in front
+    // of the sync token and the rest are the old ones, shifted
+    new_diagnostics.extend(lexer.take_diagnostics().into_iter().filter(|d| d.span.lo < token.span.lo));
+    let unchanged_suffix = (old, new_tokens.len());
+
+    match sync {
+        Some(sync) => {
+            shift.sync_line = sync.line;
+            shift.lines = token.line as isize - sync.line as isize;
+            shift.columns = token.column as isize - sync.column as isize;
+
+            new_tokens.extend(tokens[old..].iter().map(|t| shift.apply(t)));
+            new_diagnostics.extend(diagnostics.iter().filter(|d| d.span.lo >= sync.span.lo).map(|d| LexDiagnostic {
+                span: shift.shift_span(d.span),
+                ..d.clone()
+            }));
+        }
+        None => new_tokens.push(token),
+    }
+
+    Relexed {
+        tokens: new_tokens,
+        diagnostics: new_diagnostics,
+        unchanged_prefix: restart,
+        unchanged_suffix,
+        shift,
+    }
+}
@@ -1,203 +1,268 @@
-use super::token::{Token, TokenType};
+use diagnostics::{Diagnostic, FileId, Span};
 
+use super::error::{LexDiagnostic, LexErrorKind};
+use super::token::{LosslessToken, NumericSuffix, Token, TokenType, Trivia, TriviaKind};
+
+/// Scans Hydra source text into tokens.
+///
+/// The lexer works directly on the bytes of the input and produces one token
+/// per call to [`Lexer::next_token`], so it can be driven lazily as an
+/// [`Iterator`]. Lexical errors become `Error` tokens and are collected in
+/// [`Lexer::diagnostics`].
 pub struct Lexer<'a> {
     input: &'a str,
-    chars: Vec<char>,
+    file_id: FileId,
     current: usize,
     start: usize,
     line: usize,
     column: usize,
+    diagnostics: Vec<LexDiagnostic>,
+    finished: bool,
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(input: &'a str) -> Self {
+        Self::with_file_id(input, FileId::default())
+    }
+
+    pub fn with_file_id(input: &'a str, file_id: FileId) -> Self {
         Self {
             input,
-            chars: input.chars().collect(),
+            file_id,
             current: 0,
             start: 0,
             line: 1,
             column: 1,
+            diagnostics: Vec::new(),
+            finished: false,
         }
     }
 
-    pub fn tokenize(&mut self) -> Result<Vec<Token<'a>>, String> {
+    /// Continues scanning `input` from byte `offset`, which must be the start
+    /// of a token previously scanned at `line` and `column`.
+    pub(crate) fn resume(input: &'a str, file_id: FileId, offset: usize, line: usize, column: usize) -> Self {
+        Self {
+            current: offset,
+            start: offset,
+            line,
+            column,
+            ..Self::with_file_id(input, file_id)
+        }
+    }
+
+    /// Tokenizes the whole input, stopping at the first lexical error.
+    pub fn tokenize(&mut self) -> Result<Vec<Token<'a>>, Box<Diagnostic>> {
+        let (tokens, diagnostics) = self.tokenize_recovering();
+
+        match diagnostics.into_iter().next() {
+            Some(diagnostic) => Err(Box::new(diagnostic.into())),
+            None => Ok(tokens),
+        }
+    }
+
+    /// Tokenizes the whole input, replacing anything malformed with an
+    /// `Error` token and carrying on, so every lexical error is reported.
+    pub fn tokenize_recovering(&mut self) -> (Vec<Token<'a>>, Vec<LexDiagnostic>) {
+        let tokens = self.by_ref().collect();
+
+        (tokens, self.take_diagnostics())
+    }
+
+    /// Tokenizes the whole input without dropping anything: whitespace and
+    /// comments are attached as leading trivia to the token that follows
+    /// them, and trivia at the end of the input is attached to `EOF`.
+    pub fn tokenize_lossless(&mut self) -> (Vec<LosslessToken<'a>>, Vec<LexDiagnostic>) {
         let mut tokens = Vec::new();
 
-        while !self.is_at_end() {
-            self.skip_whitespace();
+        loop {
+            let token = self.next_lossless_token();
+            let is_eof = token.token.token_type == TokenType::EOF;
+            tokens.push(token);
 
-            if self.is_at_end() {
+            if is_eof {
                 break;
             }
+        }
+        self.finished = true;
+
+        (tokens, self.take_diagnostics())
+    }
 
-            self.start = self.current;
-            let start_line = self.line;
-            let start_column = self.column;
+    /// Scans the next token. Once the input is exhausted every call returns
+    /// an `EOF` token.
+    pub fn next_token(&mut self) -> Token<'a> {
+        while self.scan_trivia().is_some() {}
 
-            if let Some(token_type) = self.scan_token()? {
-                let lexeme = &self.input[self.start_offset()..self.current_offset()];
+        self.start = self.current;
+        let start_line = self.line;
+        let start_column = self.column;
 
-                tokens.push(Token {
-                    token_type,
-                    lexeme,
-                    line: start_line,
-                    column: start_column,
-                });
-            }
+        if self.is_at_end() {
+            return self.make_token(TokenType::EOF, start_line, start_column);
         }
 
-        tokens.push(Token {
-            token_type: TokenType::EOF,
-            lexeme: "",
-            line: self.line,
-            column: self.column,
-        });
+        let token_type = self.scan_token();
+        self.make_token(token_type, start_line, start_column)
+    }
 
-        Ok(tokens)
+    /// Scans the next token together with the trivia in front of it.
+    pub fn next_lossless_token(&mut self) -> LosslessToken<'a> {
+        let mut leading = Vec::new();
+        while let Some(trivia) = self.scan_trivia() {
+            leading.push(trivia);
+        }
+
+        LosslessToken { leading, token: self.next_token() }
+    }
+
+    /// The lexical errors found so far.
+    pub fn diagnostics(&self) -> &[LexDiagnostic] {
+        &self.diagnostics
     }
 
-    fn scan_token(&mut self) -> Result<Option<TokenType>, String> {
+    pub fn take_diagnostics(&mut self) -> Vec<LexDiagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    fn make_token(&self, token_type: TokenType, line: usize, column: usize) -> Token<'a> {
+        Token {
+            token_type,
+            lexeme: self.lexeme(),
+            span: self.token_span(),
+            line,
+            column,
+        }
+    }
+
+    fn scan_token(&mut self) -> TokenType {
         let c = self.advance();
 
-        let result = match c {
-            '"' => return self.scan_string(),
-            '\'' => return self.scan_char(),
-            '(' => Some(TokenType::LeftParen),
-            ')' => Some(TokenType::RightParen),
-            '{' => Some(TokenType::LeftBrace),
-            '}' => Some(TokenType::RightBrace),
-            '[' => Some(TokenType::LeftBracket),
-            ']' => Some(TokenType::RightBracket),
-            ';' => Some(TokenType::Semicolon),
+        match c {
+            '"' => if self.peek() == '"' && self.peek_next() == '"' {
+                self.advance();
+                self.advance();
+
+                self.scan_multiline_string()
+            } else {
+                self.scan_string()
+            },
+            '\'' => self.scan_char(),
+            '(' => TokenType::LeftParen,
+            ')' => TokenType::RightParen,
+            '{' => TokenType::LeftBrace,
+            '}' => TokenType::RightBrace,
+            '[' => TokenType::LeftBracket,
+            ']' => TokenType::RightBracket,
+            ';' => TokenType::Semicolon,
             '.' => {
                 if self.peek() == '.' {
                     if self.peek_next() == '.' {
                         self.advance();
                         self.advance();
 
-                        Some(TokenType::TripleDot)
+                        TokenType::TripleDot
                     } else if self.peek_next() == '=' {
                         self.advance();
                         self.advance();
 
-                        Some(TokenType::DoubleDotEqual)
+                        TokenType::DoubleDotEqual
                     } else {
                         self.advance();
 
-                        Some(TokenType::DoubleDot)
+                        TokenType::DoubleDot
                     }
+                } else if self.peek().is_ascii_digit() {
+                    self.scan_digits(10, 0);
+
+                    self.invalid_number(format!(
+                        "float literals must have an integer part, write '0{}'",
+                        self.lexeme(),
+                    ))
                 } else {
-                    Some(TokenType::Dot)
+                    TokenType::Dot
                 }
             },
             ':' => if self.match_char(':') {
-                Some(TokenType::DoubleColon)
+                TokenType::DoubleColon
             } else {
-                Some(TokenType::Colon)
+                TokenType::Colon
             },
             '&' => if self.match_char('&') {
-                Some(TokenType::DoubleAmpersand)
+                TokenType::DoubleAmpersand
             } else if self.match_char('=') {
-                Some(TokenType::AmpersandEqual)
+                TokenType::AmpersandEqual
             } else {
-                Some(TokenType::Ampersand)
+                TokenType::Ampersand
             },
             '|' => if self.match_char('|') {
-                Some(TokenType::DoublePipe)
+                TokenType::DoublePipe
             } else if self.match_char('=') {
-                Some(TokenType::PipeEqual)
+                TokenType::PipeEqual
             } else {
-                Some(TokenType::Pipe)
+                TokenType::Pipe
             },
             '^' => if self.match_char('=') {
-                Some(TokenType::CarrotEqual)
+                TokenType::CarrotEqual
             } else {
-                Some(TokenType::Carrot)
+                TokenType::Carrot
             },
-            '?' => Some(TokenType::QuestionMark),
-            ',' => Some(TokenType::Comma),
+            '?' => TokenType::QuestionMark,
+            ',' => TokenType::Comma,
             '+' => if self.match_char('=') {
-                Some(TokenType::PlusEqual)
+                TokenType::PlusEqual
             } else if self.match_char('+') {
-                Some(TokenType::PlusPlus)
+                TokenType::PlusPlus
             } else {
-                Some(TokenType::Plus)
+                TokenType::Plus
             },
             '-' => if self.match_char('=') {
-                Some(TokenType::MinusEqual)
+                TokenType::MinusEqual
             } else if self.match_char('-') {
-                Some(TokenType::MinusMinus)
+                TokenType::MinusMinus
             } else if self.match_char('>') {
-                Some(TokenType::Arrow)
+                TokenType::Arrow
             } else {
-                Some(TokenType::Minus)
+                TokenType::Minus
             },
             '*' => if self.match_char('=') {
-                Some(TokenType::StarEqual)
+                TokenType::StarEqual
             } else {
-                Some(TokenType::Star)
+                TokenType::Star
             },
-            '/' => if self.match_char('/') {
-                while self.peek() != '\n' && !self.is_at_end() {
-                    self.advance();
-                }
-
-                None
-            } else if self.match_char('*') {
-                // multi line comment, consume until '*/' or EOF
-                while !self.is_at_end() {
-                    if self.peek() == '*' && self.peek_next() == '/' {
-                        // consume '*' and '/'
-                        self.advance();
-                        self.advance();
-
-                        break;
-                    } else {
-                        if self.peek() == '\n' {
-                            self.line += 1;
-                            self.column = 0;
-                        }
-
-                        self.advance();
-                    }
-                }
-
-                None
-            } else if self.match_char('='){
-                Some(TokenType::ForwardSlashEqual)
+            '/' => if self.match_char('=') {
+                TokenType::ForwardSlashEqual
             } else {
-                Some(TokenType::ForwardSlash)
+                TokenType::ForwardSlash
             },
             '%' => if self.match_char('=') {
-                Some(TokenType::ModuloEqual)
+                TokenType::ModuloEqual
             } else {
-                Some(TokenType::Modulo)
+                TokenType::Modulo
             },
             '=' => if self.match_char('=') {
-                Some(TokenType::DoubleEqual)
+                TokenType::DoubleEqual
             } else if self.match_char('>') {
-                Some(TokenType::EqualArrow)
+                TokenType::EqualArrow
             } else {
-                Some(TokenType::Equal)
+                TokenType::Equal
             },
             '!' => if self.match_char('=') {
-                Some(TokenType::ExclamEqual)
+                TokenType::ExclamEqual
             } else {
-                Some(TokenType::ExclamationMark)
+                TokenType::ExclamationMark
             },
             '<' => {
                 if self.peek() == '<' && self.peek_next() == '=' {
                     self.advance(); // consume '<'
                     self.advance(); // consume '='
 
-                    Some(TokenType::DoubleLeftEqual)
+                    TokenType::DoubleLeftEqual
                 } else if self.match_char('<') {
-                    Some(TokenType::DoubleLeftAngle)
+                    TokenType::DoubleLeftAngle
                 } else if self.match_char('=') {
-                    Some(TokenType::LessEqual)
+                    TokenType::LessEqual
                 } else {
-                    Some(TokenType::LeftAngle)
+                    TokenType::LeftAngle
                 }
             },
             '>' => {
@@ -205,90 +270,207 @@ impl<'a> Lexer<'a> {
                     self.advance();
                     self.advance();
 
-                    Some(TokenType::DoubleRightEqual)
+                    TokenType::DoubleRightEqual
                 } else if self.match_char('>') {
-                    Some(TokenType::DoubleRightAngle)
+                    TokenType::DoubleRightAngle
                 } else if self.match_char('=') {
-                    Some(TokenType::GreaterEqual)
+                    TokenType::GreaterEqual
                 } else {
-                    Some(TokenType::RightAngle)
+                    TokenType::RightAngle
                 }
             },
             _ => {
                 if c.is_ascii_digit() {
-                    return self.scan_number(c);
+                    self.scan_number(c)
+                } else if c == 'r' && matches!(self.peek(), '"' | '#') {
+                    self.scan_raw_string()
                 } else if c.is_alphabetic() || c == '_' {
-                    return self.scan_identifier(c);
+                    self.scan_identifier(c)
                 } else {
-                    return Err(format!("Unexpected character '{}' at line {}, column {}", c, self.line, self.column));
+                    self.error(
+                        LexErrorKind::UnexpectedCharacter,
+                        self.token_span(),
+                        format!("unexpected character '{}'", c),
+                    )
                 }
             }
+        }
+    }
+
+    /// Scans a numeric literal following the grammar in grammar.md:
+    /// `0x`/`0o`/`0b` integers, decimal integers, and decimal floats with an
+    /// optional exponent, each with `_` separators and an optional suffix.
+    fn scan_number(&mut self, first_digit: char) -> TokenType {
+        let errors_before = self.diagnostics.len();
+
+        let radix = match (first_digit, self.peek()) {
+            ('0', 'x' | 'X') => 16,
+            ('0', 'o' | 'O') => 8,
+            ('0', 'b' | 'B') => 2,
+            _ => 10,
         };
 
-        Ok(result)
-    }
+        // a decimal literal's first digit has already been consumed
+        let (digits_start, leading_digits) = if radix == 10 {
+            (self.start, 1)
+        } else {
+            self.advance(); // consume the base prefix
+            (self.current, 0)
+        };
 
-    fn scan_number(&mut self, first_digit: char) -> Result<Option<TokenType>, String> {
-        // Check for hex or binary
-        if first_digit == '0' {
-            match self.peek() {
-                'x' | 'X' => {
-                    self.advance(); // consume 'x'
-                    while self.peek().is_ascii_hexdigit() || self.peek() == '_' {
-                        self.advance();
-                    }
-                    let lexeme = &self.input[self.start_offset()..self.current_offset()];
-                    let value = i64::from_str_radix(&lexeme[2..].replace('_', ""), 16)
-                        .map_err(|_| format!("Invalid hexadecimal literal: '{}'", lexeme))?;
-                    return Ok(Some(TokenType::IntLiteral(value)));
+        if self.scan_digits(radix, leading_digits) == 0 && self.diagnostics.len() == errors_before {
+            let prefix = &self.input[self.start..self.current];
+            return self.invalid_number(format!("missing digits after the integer base prefix '{}'", prefix));
+        }
+
+        let mut is_float = false;
+        if radix == 10 {
+            if self.peek() == '.' && self.peek_next().is_ascii_digit() {
+                self.advance(); // consume the '.'
+                self.scan_digits(10, 0);
+                is_float = true;
+            } else if self.peek() == '.' && !matches!(self.peek_next(), '.' | '_') && !self.peek_next().is_alphabetic() {
+                self.advance(); // consume the '.'
+
+                return self.invalid_number(format!(
+                    "expected digits after the decimal point, write '{}0'",
+                    self.lexeme(),
+                ));
+            }
+
+            if matches!(self.peek(), 'e' | 'E') {
+                self.advance(); // consume the 'e'
+                if matches!(self.peek(), '+' | '-') {
+                    self.advance();
                 }
-                'b' => {
-                    self.advance(); // consume 'b'
-                    while self.peek() == '0' || self.peek() == '1' || self.peek() == '_' {
-                        self.advance();
-                    }
-                    let lexeme = &self.input[self.start_offset()..self.current_offset()];
-                    let value = i64::from_str_radix(&lexeme[2..].replace('_', ""), 2)
-                        .map_err(|_| format!("Invalid binary literal: '{}'", lexeme))?;
-                    return Ok(Some(TokenType::IntLiteral(value)));
+
+                if self.scan_digits(10, 0) == 0 {
+                    return self.invalid_number(format!(
+                        "expected at least one digit in exponent: '{}'",
+                        self.lexeme(),
+                    ));
                 }
-                _ => {}
+                is_float = true;
             }
         }
 
-        // --- Decimal / Float path ---
-        while self.peek().is_ascii_digit() || self.peek() == '_' {
-            self.advance();
+        let digits = self.input[digits_start..self.current].replace('_', "");
+        let suffix = match self.scan_numeric_suffix() {
+            Ok(suffix) => suffix,
+            Err(error) => return error,
+        };
+
+        if self.diagnostics.len() > errors_before {
+            return TokenType::Error;
         }
 
-        // After consuming the whole number part, check if it's a float
-        if self.peek() == '.' && self.peek_next().is_ascii_digit() {
-            self.advance(); // Consume the '.'
+        let lexeme = self.lexeme();
+        let kind = match radix {
+            16 => "hexadecimal",
+            8 => "octal",
+            2 => "binary",
+            _ => "integer",
+        };
+
+        if is_float || suffix.is_some_and(|s| s.is_float()) {
+            if radix != 10 {
+                return self.invalid_number(format!("{} float literal is not supported: '{}'", kind, lexeme));
+            }
+            if let Some(suffix) = suffix.filter(|s| !s.is_float()) {
+                return self.invalid_number(format!("integer suffix '{}' on float literal: '{}'", suffix.as_str(), lexeme));
+            }
+
+            return match digits.parse() {
+                Ok(value) => TokenType::FloatLiteral { value, suffix },
+                Err(_) => self.invalid_number(format!("invalid float literal: '{}'", lexeme)),
+            };
+        }
+
+        match u128::from_str_radix(&digits, radix) {
+            Ok(value) => TokenType::IntLiteral { value, suffix },
+            Err(_) => self.invalid_number(format!("{} literal is too large: '{}'", kind, lexeme)),
+        }
+    }
+
+    /// Consumes a run of digits in `radix`, allowing a single `_` between two
+    /// digits. Misplaced separators and decimal digits that are out of range
+    /// for `radix` are reported individually. `digits` counts digits of the
+    /// run that were already consumed; the total is returned.
+    fn scan_digits(&mut self, radix: u32, mut digits: usize) -> usize {
+        let mut separator: Option<usize> = None;
+
+        loop {
+            let c = self.peek();
+            let position = self.current;
+
+            if c == '_' {
+                self.advance();
 
-            while self.peek().is_ascii_digit() {
+                if digits == 0 {
+                    self.number_error_at(position, "digit separator '_' must follow a digit");
+                } else if separator.is_some() {
+                    self.number_error_at(position, "consecutive digit separators '__'");
+                }
+                separator = Some(position);
+            } else if c.is_digit(radix) {
+                self.advance();
+                digits += 1;
+                separator = None;
+            } else if c.is_ascii_digit() {
                 self.advance();
+                let kind = if radix == 8 { "octal" } else { "binary" };
+                self.number_error_at(position, &format!("invalid digit '{}' in {} literal", c, kind));
+            } else {
+                break;
             }
+        }
 
-            let lexeme = &self.input[self.start_offset()..self.current_offset()];
-            let value: f64 = lexeme.replace('_', "").parse()
-                .map_err(|_| format!("Invalid float literal: '{}'", lexeme))?;
-            Ok(Some(TokenType::FloatLiteral(value)))
-        } else {
-            let lexeme = &self.input[self.start_offset()..self.current_offset()];
-            let value: i64 = lexeme.replace('_', "").parse()
-                .map_err(|_| format!("Invalid integer literal: '{}'", lexeme))?;
-            Ok(Some(TokenType::IntLiteral(value)))
+        if let Some(position) = separator.filter(|_| digits > 0) {
+            self.number_error_at(position, "digit separator '_' must be followed by a digit");
+        }
+
+        digits
+    }
+
+    fn number_error_at(&mut self, position: usize, message: &str) {
+        let end = position + self.input[position..].chars().next().map_or(0, char::len_utf8);
+
+        self.error(
+            LexErrorKind::InvalidNumber,
+            Span::new(self.file_id, position, end),
+            message.to_string(),
+        );
+    }
+
+    /// Scans an optional type suffix directly after the digits of a number.
+    fn scan_numeric_suffix(&mut self) -> Result<Option<NumericSuffix>, TokenType> {
+        if !self.peek().is_alphabetic() {
+            return Ok(None);
+        }
+
+        let suffix_start = self.current;
+        while self.peek().is_alphanumeric() || self.peek() == '_' {
+            self.advance();
         }
+
+        let text = &self.input[suffix_start..self.current];
+        NumericSuffix::from_name(text)
+            .map(Some)
+            .ok_or_else(|| self.invalid_number(format!("invalid suffix '{}' for number literal", text)))
     }
 
-    fn scan_identifier(&mut self, _first: char) -> Result<Option<TokenType>, String> {
+    fn invalid_number(&mut self, message: String) -> TokenType {
+        self.error(LexErrorKind::InvalidNumber, self.token_span(), message)
+    }
+
+    fn scan_identifier(&mut self, _first: char) -> TokenType {
         while self.peek().is_alphanumeric() || self.peek() == '_' {
             self.advance();
         }
 
-        let text = &self.input[self.start_offset()..self.current_offset()];
+        let text = self.lexeme();
 
-        Ok(Some(self.get_keyword_or_identifier(text)))
+        self.get_keyword_or_identifier(text)
     }
 
     fn get_keyword_or_identifier(&self, text: &str) -> TokenType {
@@ -336,147 +518,508 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    /// Consumes one character. Columns are 1-based and count `char`s; a
+    /// newline moves on to column 1 of the next line.
     fn advance(&mut self) -> char {
-        let c = self.chars[self.current];
-        self.current += 1;
-        self.column += 1;
+        let c = self.peek();
+        self.current += c.len_utf8();
+
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
 
         c
     }
 
+    /// Advances to byte offset `end`.
+    fn advance_to(&mut self, end: usize) {
+        while self.current < end && !self.is_at_end() {
+            self.advance();
+        }
+    }
+
     fn match_char(&mut self, expected: char) -> bool {
-        if self.is_at_end() || self.chars[self.current] != expected {
+        if self.is_at_end() || self.peek() != expected {
             false
         } else {
-            self.current += 1;
-            self.column += 1;
+            self.advance();
             true
         }
     }
 
     fn peek(&self) -> char {
-        if self.is_at_end() {
-            '\0'
-        } else {
-            self.chars[self.current]
-        }
+        self.input[self.current..].chars().next().unwrap_or('\0')
     }
 
     fn peek_next(&self) -> char {
-        if self.current + 1 >= self.chars.len() {
-            '\0'
-        } else {
-            self.chars[self.current + 1]
-        }
+        self.input[self.current..].chars().nth(1).unwrap_or('\0')
     }
 
     fn is_at_end(&self) -> bool {
-        self.current >= self.chars.len()
+        self.current >= self.input.len()
     }
 
-    fn skip_whitespace(&mut self) {
-        while !self.is_at_end() {
-            match self.peek() {
-                ' ' | '\r' | '\t' => {
-                    self.advance();
-                },
-                '\n' => {
-                    self.line += 1;
-                    self.column = 0;
+    /// Scans one run of whitespace or one comment, if the input continues
+    /// with either.
+    fn scan_trivia(&mut self) -> Option<Trivia<'a>> {
+        self.start = self.current;
+
+        let kind = match (self.peek(), self.peek_next()) {
+            (' ' | '\r' | '\t' | '\n', _) => {
+                while !self.is_at_end() {
+                    match self.peek() {
+                        ' ' | '\r' | '\t' | '\n' => {
+                            self.advance();
+                        },
+                        _ => break,
+                    }
+                }
+
+                TriviaKind::Whitespace
+            }
+            ('/', '/') => {
+                while self.peek() != '\n' && !self.is_at_end() {
                     self.advance();
-                },
-                _ => break,
+                }
+
+                // `///` is a doc comment, but `////...` is an ordinary one
+                let text = self.lexeme();
+                if text.starts_with("///") && !text.starts_with("////") {
+                    TriviaKind::DocLineComment
+                } else {
+                    TriviaKind::LineComment
+                }
             }
-        }
+            ('/', '*') => {
+                self.advance();
+                self.advance();
+
+                // multi line comment, consume until the matching '*/'
+                let mut depth = 1;
+                while depth > 0 && !self.is_at_end() {
+                    if self.peek() == '*' && self.peek_next() == '/' {
+                        self.advance();
+                        self.advance();
+                        depth -= 1;
+                    } else if self.peek() == '/' && self.peek_next() == '*' {
+                        self.advance();
+                        self.advance();
+                        depth += 1;
+                    } else {
+                        self.advance();
+                    }
+                }
+
+                if depth > 0 {
+                    self.error(
+                        LexErrorKind::UnterminatedBlockComment,
+                        Span::new(self.file_id, self.start, self.start + 2),
+                        "unterminated block comment".to_string(),
+                    );
+                }
+
+                // `/** */` is a doc comment, but `/**/` and `/*** */` are not
+                let text = self.lexeme();
+                if text.starts_with("/**") && !text.starts_with("/***") && text != "/**/" {
+                    TriviaKind::DocBlockComment
+                } else {
+                    TriviaKind::BlockComment
+                }
+            }
+            _ => return None,
+        };
+
+        Some(Trivia { kind, text: self.lexeme(), span: self.token_span() })
     }
 
-    fn scan_string(&mut self) -> Result<Option<TokenType>, String> {
-        let mut value = String::new();
+    fn scan_string(&mut self) -> TokenType {
+        let mut value = Vec::new();
+        let mut valid = true;
 
         while !self.is_at_end() {
+            let escape_start = self.current;
             let c = self.advance();
 
             match c {
                 '"' => {
-                    return Ok(Some(TokenType::StringLiteral(value)));
-                }
-                '\\' => {
-                    let escaped = match self.advance() {
-                        'n' => '\n', // new line
-                        'r' => '\r', // move cursor to beginnning of line
-                        't' => '\t', // tab
-                        '"' => '"',
-                        '\\' => '\\',
-
-                        other => return Err(format!("Invalid escape sequence: '\\{}'", other)),
+                    return if valid {
+                        TokenType::StringLiteral(value)
+                    } else {
+                        TokenType::Error
                     };
-
-                    value.push(escaped);
                 }
-                '\n' => {
-                    self.line += 1;
-                    self.column = 0;
+                '\\' => {
+                    if self.is_at_end() {
+                        break;
+                    }
 
-                    value.push('\n');
+                    match self.scan_escape(escape_start) {
+                        Some(escaped) => escaped.push_to(&mut value),
+                        None => valid = false,
+                    }
                 }
                 _ => {
-                    value.push(c);
+                    push_char(&mut value, c);
                 }
             }
         }
 
-        Err(format!(
-            "Unterminated string at line {}, column {}",
-            self.line, self.column
-        ))
+        self.error(
+            LexErrorKind::UnterminatedString,
+            self.opening_delimiter_span(),
+            "unterminated string".to_string(),
+        )
     }
 
-    fn scan_char(&mut self) -> Result<Option<TokenType>, String> {
-        if self.is_at_end() {
-            return Err(format!(
-                "Unterminated char at line {}, column {}",
-                self.line, self.column
-            ));
+    /// Scans a raw string, `r"..."` or `r#"..."#` with any number of `#`s.
+    /// Nothing is escaped; the string ends at the first `"` followed by as
+    /// many `#`s as it was opened with.
+    fn scan_raw_string(&mut self) -> TokenType {
+        let mut hashes = 0;
+        while self.match_char('#') {
+            hashes += 1;
         }
 
-        let c = match self.advance() {
-            '\\' => {
+        if !self.match_char('"') {
+            return self.error(
+                LexErrorKind::UnterminatedString,
+                self.token_span(),
+                "expected '\"' to open raw string".to_string(),
+            );
+        }
+
+        let closing = format!("\"{}", "#".repeat(hashes));
+        let content_start = self.current;
+
+        match self.input[content_start..].find(&closing) {
+            Some(offset) => {
+                let content_end = content_start + offset;
+                self.advance_to(content_end + closing.len());
+
+                TokenType::StringLiteral(self.input.as_bytes()[content_start..content_end].to_vec())
+            }
+            None => {
+                let opening = Span::new(self.file_id, self.start, content_start);
+                self.advance_to(self.input.len());
+
+                self.error(LexErrorKind::UnterminatedString, opening, "unterminated raw string".to_string())
+            }
+        }
+    }
+
+    /// Scans a multi-line string after its opening `"""`. The opening `"""`
+    /// must end its line and the closing `"""` must sit on a line of its own;
+    /// the closing line's indentation is stripped from every content line,
+    /// and the newlines after the opening and before the closing delimiter
+    /// are not part of the value. Escapes work as in ordinary strings.
+    fn scan_multiline_string(&mut self) -> TokenType {
+        let opening = self.token_span();
+
+        let rest = &self.input[self.current..];
+        match rest.find('\n') {
+            Some(line_end) if rest[..line_end].trim().is_empty() => self.advance_to(self.current + line_end + 1),
+            line_end => {
+                // recover by skipping a closing `"""` on the same line, if any
+                let line_end = line_end.unwrap_or(rest.len());
+                if let Some(closing) = find_multiline_closing(&rest[..line_end]) {
+                    self.advance_to(self.current + closing + 3);
+                }
+
+                return self.error(
+                    LexErrorKind::InvalidMultilineString,
+                    opening,
+                    "multi-line string content must start on a new line after '\"\"\"'".to_string(),
+                );
+            }
+        }
+
+        let content_start = self.current;
+        let Some(closing) = find_multiline_closing(&self.input[content_start..]) else {
+            self.advance_to(self.input.len());
+
+            return self.error(LexErrorKind::UnterminatedString, opening, "unterminated multi-line string".to_string());
+        };
+        let closing = content_start + closing;
+
+        // `content_start` follows a newline, so the closing line starts no earlier
+        let closing_line = self.input[..closing].rfind('\n').map_or(0, |i| i + 1);
+        let indent = &self.input[closing_line..closing];
+        if !indent.chars().all(|c| c == ' ' || c == '\t') {
+            self.advance_to(closing + 3);
+
+            return self.error(
+                LexErrorKind::InvalidMultilineString,
+                Span::new(self.file_id, closing, closing + 3),
+                "closing '\"\"\"' must be on its own line".to_string(),
+            );
+        }
+
+        let mut value = Vec::new();
+        let mut valid = true;
+
+        while self.current < closing_line {
+            let line_end = self.current + self.input[self.current..].find('\n').unwrap_or(0);
+            let line = &self.input[self.current..line_end];
+            let content_end = if line.ends_with('\r') { line_end - 1 } else { line_end };
+
+            if line.trim().is_empty() {
+                self.advance_to(content_end);
+            } else if line.starts_with(indent) {
+                self.advance_to(self.current + indent.len());
+            } else {
+                let whitespace = line.len() - line.trim_start().len();
+                self.error(
+                    LexErrorKind::InvalidMultilineString,
+                    Span::new(self.file_id, self.current, self.current + whitespace.max(1)),
+                    "line in multi-line string is indented less than its closing '\"\"\"'".to_string(),
+                );
+                valid = false;
+            }
+
+            while self.current < content_end {
+                let escape_start = self.current;
+
                 match self.advance() {
-                    'n' => '\n',
-                    'r' => '\r',
-                    't' => '\t',
-                    '\'' => '\'',
-                    '\\' => '\\',
-
-                    other => {
-                        return Err(format!(
-                            "Invalid escape sequence: '\\{}' at line {}, column {}",
-                            other, self.line, self.column
-                        ));
+                    '\\' if self.current == content_end => {
+                        self.escape_error(escape_start, "expected an escape sequence after '\\'".to_string());
+                        valid = false;
                     }
+                    '\\' => match self.scan_escape(escape_start) {
+                        Some(escaped) => escaped.push_to(&mut value),
+                        None => valid = false,
+                    },
+                    c => push_char(&mut value, c),
                 }
             }
 
-            other => other,
+            self.advance_to(line_end + 1);
+            if self.current < closing_line {
+                value.push(b'\n');
+            }
+        }
+
+        self.advance_to(closing + 3);
+
+        if valid {
+            TokenType::StringLiteral(value)
+        } else {
+            TokenType::Error
+        }
+    }
+
+    fn scan_char(&mut self) -> TokenType {
+        if self.is_at_end() {
+            return self.error(
+                LexErrorKind::UnterminatedChar,
+                self.opening_delimiter_span(),
+                "unterminated char".to_string(),
+            );
+        }
+
+        let escape_start = self.current;
+        let c = match self.advance() {
+            '\\' if !self.is_at_end() => self.scan_escape(escape_start).map(Escaped::into_char),
+
+            '\'' => {
+                return self.error(
+                    LexErrorKind::InvalidCharLiteral,
+                    self.token_span(),
+                    "empty char literal".to_string(),
+                );
+            }
+
+            other => Some(other),
         };
 
         if self.peek() != '\'' {
-            return Err(format!(
-                "Unterminated or multi-character literal at line {}, column {}",
-                self.line, self.column
-            ));
+            // Recover by skipping to a closing quote on the same line, if any.
+            let rest = &self.input[self.current..];
+            let line_end = rest.find('\n').unwrap_or(rest.len());
+
+            return match rest[..line_end].find('\'') {
+                Some(close) => {
+                    let closing_quote = self.current + close;
+                    while self.current <= closing_quote {
+                        self.advance();
+                    }
+
+                    self.error(
+                        LexErrorKind::InvalidCharLiteral,
+                        self.token_span(),
+                        "char literal must contain exactly one character".to_string(),
+                    )
+                }
+                None => self.error(
+                    LexErrorKind::UnterminatedChar,
+                    self.opening_delimiter_span(),
+                    "unterminated or multi-character literal".to_string(),
+                ),
+            };
         }
 
         self.advance(); // consume closing '
 
-        Ok(Some(TokenType::CharLiteral(c)))
+        c.map_or(TokenType::Error, TokenType::CharLiteral)
+    }
+
+    /// Scans the escape sequence following a `\\` at `escape_start`, shared by
+    /// string and char literals. Reports an error spanning the whole escape
+    /// and returns `None` if it is malformed.
+    fn scan_escape(&mut self, escape_start: usize) -> Option<Escaped> {
+        let escaped = match self.advance() {
+            'n' => '\n', // new line
+            'r' => '\r', // move cursor to beginnning of line
+            't' => '\t', // tab
+            '0' => '\0',
+            '"' => '"',
+            '\'' => '\'',
+            '\\' => '\\',
+            'x' => return self.scan_hex_escape(escape_start),
+            'u' => return self.scan_unicode_escape(escape_start),
+
+            other => {
+                return self.escape_error(escape_start, format!("invalid escape sequence: '\\{}'", other));
+            }
+        };
+
+        Some(Escaped::Char(escaped))
+    }
+
+    /// `\xNN`: a byte given by exactly two hex digits.
+    fn scan_hex_escape(&mut self, escape_start: usize) -> Option<Escaped> {
+        let digits_start = self.current;
+        while self.current - digits_start < 2 && self.peek().is_ascii_hexdigit() {
+            self.advance();
+        }
+
+        let digits = &self.input[digits_start..self.current];
+        if digits.len() < 2 {
+            return self.escape_error(escape_start, "numeric character escape is too short, expected '\\xNN'".to_string());
+        }
+
+        u8::from_str_radix(digits, 16).ok().map(Escaped::Byte)
     }
 
-    fn start_offset(&self) -> usize {
-        self.chars[..self.start].iter().map(|c| c.len_utf8()).sum()
+    /// `\u{N..}`: one to six hex digits naming a Unicode scalar value.
+    fn scan_unicode_escape(&mut self, escape_start: usize) -> Option<Escaped> {
+        if !self.match_char('{') {
+            return self.escape_error(escape_start, "incorrect unicode escape sequence, expected '\\u{...}'".to_string());
+        }
+
+        let digits_start = self.current;
+        while self.peek().is_ascii_hexdigit() {
+            self.advance();
+        }
+        let digits = &self.input[digits_start..self.current];
+
+        if !self.match_char('}') {
+            return self.escape_error(escape_start, "unterminated unicode escape, expected '}'".to_string());
+        }
+        if digits.is_empty() {
+            return self.escape_error(escape_start, "empty unicode escape".to_string());
+        }
+        if digits.len() > 6 {
+            return self.escape_error(escape_start, "overlong unicode escape, expected at most 6 hex digits".to_string());
+        }
+
+        let value = u32::from_str_radix(digits, 16).ok()?;
+        if (0xD800..=0xDFFF).contains(&value) {
+            return self.escape_error(escape_start, format!("invalid unicode escape '\\u{{{}}}', surrogates are not scalar values", digits));
+        }
+
+        char::from_u32(value).map(Escaped::Char).or_else(|| {
+            self.escape_error(escape_start, format!("invalid unicode escape '\\u{{{}}}', must be at most 10FFFF", digits))
+        })
     }
 
-    fn current_offset(&self) -> usize {
-        self.chars[..self.current].iter().map(|c| c.len_utf8()).sum()
+    fn escape_error(&mut self, escape_start: usize, message: String) -> Option<Escaped> {
+        self.error(
+            LexErrorKind::InvalidEscape,
+            Span::new(self.file_id, escape_start, self.current),
+            message,
+        );
+
+        None
     }
+
+    fn error(&mut self, kind: LexErrorKind, span: Span, message: String) -> TokenType {
+        self.diagnostics.push(LexDiagnostic { span, kind, message });
+
+        TokenType::Error
+    }
+
+    fn token_span(&self) -> Span {
+        Span::new(self.file_id, self.start, self.current)
+    }
+
+    fn opening_delimiter_span(&self) -> Span {
+        Span::new(self.file_id, self.start, self.start + 1)
+    }
+
+    fn lexeme(&self) -> &'a str {
+        &self.input[self.start..self.current]
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Token<'a>;
+
+    /// Yields every token up to and including `EOF`, then `None`.
+    fn next(&mut self) -> Option<Token<'a>> {
+        if self.finished {
+            return None;
+        }
+
+        let token = self.next_token();
+        self.finished = token.token_type == TokenType::EOF;
+
+        Some(token)
+    }
+}
+
+/// Finds the closing `"""` of a multi-line string in `content`, skipping
+/// over escaped characters so `\"""` does not end the string.
+fn find_multiline_closing(content: &str) -> Option<usize> {
+    let mut chars = content.char_indices();
+
+    while let Some((i, c)) = chars.next() {
+        if c == '\\' {
+            chars.next();
+        } else if content[i..].starts_with("\"\"\"") {
+            return Some(i);
+        }
+    }
+
+    None
+}
+
+/// A decoded escape sequence. `\xNN` gives a byte rather than a character,
+/// so a string can hold bytes that are not valid UTF-8.
+enum Escaped {
+    Char(char),
+    Byte(u8),
+}
+
+impl Escaped {
+    fn push_to(self, value: &mut Vec<u8>) {
+        match self {
+            Escaped::Char(c) => push_char(value, c),
+            Escaped::Byte(byte) => value.push(byte),
+        }
+    }
+
+    /// In a char literal, a byte is the character with that value.
+    fn into_char(self) -> char {
+        match self {
+            Escaped::Char(c) => c,
+            Escaped::Byte(byte) => char::from(byte),
+        }
+    }
+}
+
+fn push_char(value: &mut Vec<u8>, c: char) {
+    value.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
 }
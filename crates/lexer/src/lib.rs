@@ -1,8 +1,13 @@
+pub mod error;
+pub mod incremental;
 pub mod lexer;
 pub mod token;
 
+pub use diagnostics::{FileId, Location, SourceFile, SourceMap, Span};
+pub use error::{LexDiagnostic, LexErrorKind};
+pub use incremental::{relex, Relexed, TextEdit, TokenShift};
 pub use lexer::Lexer;
-pub use token::{Token, TokenType};
+pub use token::{LosslessToken, NumericSuffix, Token, TokenType, Trivia, TriviaKind};
 
 #[cfg(test)]
 mod tests {
@@ -38,9 +43,290 @@ mod tests {
         // Expect one string literal token plus EOF
         assert_eq!(tokens.len(), 2);
         match &tokens[0].token_type {
-            TokenType::StringLiteral(s) => assert_eq!(s, "hello\nworld"),
+            TokenType::StringLiteral(s) => assert_eq!(s, b"hello\nworld"),
             other => panic!("Expected string literal, got {:?}", other),
         }
         assert!(matches!(tokens[1].token_type, TokenType::EOF));
     }
+
+    #[test]
+    fn test_token_spans() {
+        let mut source_map = SourceMap::new();
+        let file_id = source_map.add_file("spans.hydra", "let é = 'ü';\nfoo");
+        let mut lexer = Lexer::with_file_id(source_map.source(file_id), file_id);
+        let tokens = lexer.tokenize().expect("Failed to tokenize");
+
+        for token in &tokens {
+            assert_eq!(token.span.file_id, file_id);
+            assert_eq!(source_map.snippet(token.span), token.lexeme);
+        }
+
+        let foo = &tokens[5];
+        assert_eq!(foo.lexeme, "foo");
+        assert_eq!(foo.span, Span::new(file_id, 15, 18));
+
+        let (lo, hi) = source_map.lookup(foo.span);
+        assert_eq!(lo, Location { line: 2, column: 1 });
+        assert_eq!(hi, Location { line: 2, column: 4 });
+        assert_eq!(source_map.file(file_id).line_text(1), Some("let é = 'ü';"));
+        assert_eq!(source_map.lookup(tokens[3].span).0, Location { line: 1, column: 9 });
+    }
+
+    #[test]
+    fn test_recovering_reports_every_error() {
+        let source = "let a = 1 # 2;\nlet s = \"bad \\q escape\";\nlet c = 'ab';\nlet d = $;";
+        let mut lexer = Lexer::new(source);
+        let (tokens, diagnostics) = lexer.tokenize_recovering();
+
+        let kinds: Vec<_> = diagnostics.iter().map(|d| d.kind).collect();
+        assert_eq!(kinds, vec![
+            LexErrorKind::UnexpectedCharacter,
+            LexErrorKind::InvalidEscape,
+            LexErrorKind::InvalidCharLiteral,
+            LexErrorKind::UnexpectedCharacter,
+        ]);
+        assert_eq!(&source[diagnostics[1].span.lo..diagnostics[1].span.hi], "\\q");
+
+        let errors = tokens.iter().filter(|t| t.token_type == TokenType::Error).count();
+        assert_eq!(errors, 4);
+        assert_eq!(tokens[tokens.len() - 2].token_type, TokenType::Semicolon);
+        assert!(Lexer::new(source).tokenize().is_err());
+    }
+
+    #[test]
+    fn test_streaming_lexer() {
+        let mut lexer = Lexer::new("x = é;");
+
+        assert_eq!(lexer.next_token().token_type, TokenType::Identifier("x".to_string()));
+
+        let rest: Vec<_> = lexer.by_ref().map(|t| t.token_type).collect();
+        assert_eq!(rest, vec![
+            TokenType::Equal,
+            TokenType::Identifier("é".to_string()),
+            TokenType::Semicolon,
+            TokenType::EOF,
+        ]);
+        assert_eq!(lexer.next(), None);
+        assert!(lexer.diagnostics().is_empty());
+    }
+
+    #[test]
+    fn test_escape_sequences() {
+        let source = r#""a\0b\x41\u{1F600}\'\xFF" '\"' '\u{e9}' '\x7f' '\xE9'"#;
+        let tokens = Lexer::new(source).tokenize().expect("Failed to tokenize");
+        let token_types: Vec<_> = tokens.iter().map(|t| &t.token_type).collect();
+
+        assert_eq!(token_types, vec![
+            &TokenType::StringLiteral(b"a\0bA\xF0\x9F\x98\x80'\xFF".to_vec()),
+            &TokenType::CharLiteral('"'),
+            &TokenType::CharLiteral('é'),
+            &TokenType::CharLiteral('\x7f'),
+            &TokenType::CharLiteral('é'),
+            &TokenType::EOF,
+        ]);
+    }
+
+    #[test]
+    fn test_invalid_escape_spans() {
+        let source = r#""\xG1 \u{D800} \u{110000} \u{} \u12 \x4" '\q'"#;
+        let (tokens, diagnostics) = Lexer::new(source).tokenize_recovering();
+
+        let escapes: Vec<_> = diagnostics.iter().map(|d| &source[d.span.lo..d.span.hi]).collect();
+        assert_eq!(escapes, vec![r"\x", r"\u{D800}", r"\u{110000}", r"\u{}", r"\u", r"\x4", r"\q"]);
+        assert!(diagnostics.iter().all(|d| d.kind == LexErrorKind::InvalidEscape));
+        assert_eq!(tokens[0].token_type, TokenType::Error);
+        assert_eq!(tokens[1].token_type, TokenType::Error);
+    }
+
+    #[test]
+    fn test_numeric_suffixes() {
+        let source = "255u8 1_000i64 2.5f32 1f64 0xFFFF_FFFF_FFFF_FFFF 42";
+        let tokens = Lexer::new(source).tokenize().expect("Failed to tokenize");
+        let token_types: Vec<_> = tokens.iter().map(|t| &t.token_type).collect();
+
+        assert_eq!(token_types, vec![
+            &TokenType::IntLiteral { value: 255, suffix: Some(NumericSuffix::U8) },
+            &TokenType::IntLiteral { value: 1000, suffix: Some(NumericSuffix::I64) },
+            &TokenType::FloatLiteral { value: 2.5, suffix: Some(NumericSuffix::F32) },
+            &TokenType::FloatLiteral { value: 1.0, suffix: Some(NumericSuffix::F64) },
+            &TokenType::IntLiteral { value: u64::MAX as u128, suffix: None },
+            &TokenType::IntLiteral { value: 42, suffix: None },
+            &TokenType::EOF,
+        ]);
+
+        let (_, diagnostics) = Lexer::new("2.5i32 3u7 0x1f64").tokenize_recovering();
+        assert_eq!(diagnostics.len(), 2);
+        assert!(diagnostics.iter().all(|d| d.kind == LexErrorKind::InvalidNumber));
+    }
+
+    #[test]
+    fn test_numeric_literal_grammar() {
+        let source = "6.022e23 1E-3 2e+2f32 0o17 0B101 0XfF 1_000.000_1 0..10";
+        let tokens = Lexer::new(source).tokenize().expect("Failed to tokenize");
+        let token_types: Vec<_> = tokens.iter().map(|t| &t.token_type).collect();
+
+        assert_eq!(token_types, vec![
+            &TokenType::FloatLiteral { value: 6.022e23, suffix: None },
+            &TokenType::FloatLiteral { value: 1e-3, suffix: None },
+            &TokenType::FloatLiteral { value: 200.0, suffix: Some(NumericSuffix::F32) },
+            &TokenType::IntLiteral { value: 0o17, suffix: None },
+            &TokenType::IntLiteral { value: 0b101, suffix: None },
+            &TokenType::IntLiteral { value: 0xff, suffix: None },
+            &TokenType::FloatLiteral { value: 1000.0001, suffix: None },
+            &TokenType::IntLiteral { value: 0, suffix: None },
+            &TokenType::DoubleDot,
+            &TokenType::IntLiteral { value: 10, suffix: None },
+            &TokenType::EOF,
+        ]);
+    }
+
+    #[test]
+    fn test_malformed_numbers() {
+        let source = "0x; 1e; 1__0; _1; 1_; 0b102; 0o8; .5; 1.;";
+        let (tokens, diagnostics) = Lexer::new(source).tokenize_recovering();
+
+        let spans: Vec<_> = diagnostics.iter().map(|d| &source[d.span.lo..d.span.hi]).collect();
+        assert_eq!(spans, vec!["0x", "1e", "_", "_", "2", "8", ".5", "1."]);
+        assert!(diagnostics.iter().all(|d| d.kind == LexErrorKind::InvalidNumber));
+        assert_eq!(diagnostics[2].message, "consecutive digit separators '__'");
+
+        // `_1` is an identifier, not a number
+        assert!(tokens.iter().any(|t| t.token_type == TokenType::Identifier("_1".to_string())));
+    }
+
+    #[test]
+    fn test_raw_strings() {
+        let source = "r\"C:\\dir\\n\" r#\"say \"hi\"\"# r##\"a\"#b\"## rx\n;";
+        let tokens = Lexer::new(source).tokenize().expect("Failed to tokenize");
+        let token_types: Vec<_> = tokens.iter().map(|t| &t.token_type).collect();
+
+        assert_eq!(token_types, vec![
+            &TokenType::StringLiteral(br"C:\dir\n".to_vec()),
+            &TokenType::StringLiteral(br#"say "hi""#.to_vec()),
+            &TokenType::StringLiteral(br##"a"#b"##.to_vec()),
+            &TokenType::Identifier("rx".to_string()),
+            &TokenType::Semicolon,
+            &TokenType::EOF,
+        ]);
+        assert_eq!((tokens[4].line, tokens[4].column), (2, 1));
+
+        let (_, diagnostics) = Lexer::new("let s = r#\"open\";").tokenize_recovering();
+        assert_eq!(diagnostics[0].kind, LexErrorKind::UnterminatedString);
+        assert_eq!((diagnostics[0].span.lo, diagnostics[0].span.hi), (8, 11));
+    }
+
+    #[test]
+    fn test_multiline_strings() {
+        let source = "let s = \"\"\"\n    {\n        \"name\": \"hydra\\t\"\n\n    }\n    \"\"\";\nx";
+        let tokens = Lexer::new(source).tokenize().expect("Failed to tokenize");
+
+        assert_eq!(
+            tokens[3].token_type,
+            TokenType::StringLiteral(b"{\n    \"name\": \"hydra\t\"\n\n}".to_vec()),
+        );
+        assert_eq!((tokens[4].line, tokens[4].column), (6, 8));
+        assert_eq!((tokens[5].line, tokens[5].column), (7, 1));
+
+        let source = "\"\"\"\n  a\n b\n  \"\"\" \"\"\"x\"\"\"";
+        let (_, diagnostics) = Lexer::new(source).tokenize_recovering();
+        let spans: Vec<_> = diagnostics.iter().map(|d| &source[d.span.lo..d.span.hi]).collect();
+        assert_eq!(spans, vec![" ", "\"\"\""]);
+        assert!(diagnostics.iter().all(|d| d.kind == LexErrorKind::InvalidMultilineString));
+    }
+
+    #[test]
+    fn test_lossless_tokens() {
+        let source = "/// Entry point.\nfn main() -> void { // body\n\t/** doc */ /* plain */ /**/ let x = 1 $;\n//// not doc\n}\n  ";
+        let (tokens, diagnostics) = Lexer::new(source).tokenize_lossless();
+
+        let text: String = tokens.iter().map(LosslessToken::text).collect();
+        assert_eq!(text, source);
+        assert_eq!(diagnostics.len(), 1);
+
+        let kinds: Vec<_> = tokens.iter().flat_map(|t| &t.leading).filter(|t| t.kind != TriviaKind::Whitespace).map(|t| t.kind).collect();
+        assert_eq!(kinds, vec![
+            TriviaKind::DocLineComment,
+            TriviaKind::LineComment,
+            TriviaKind::DocBlockComment,
+            TriviaKind::BlockComment,
+            TriviaKind::BlockComment,
+            TriviaKind::LineComment,
+        ]);
+
+        let fn_token = &tokens[0];
+        assert_eq!(fn_token.token.token_type, TokenType::Function);
+        assert_eq!(fn_token.leading[0].text, "/// Entry point.");
+        assert_eq!(fn_token.leading[0].span, Span::new(FileId::default(), 0, 16));
+
+        let eof = tokens.last().unwrap();
+        assert_eq!(eof.token.token_type, TokenType::EOF);
+        assert_eq!(eof.leading[0].text, "\n  ");
+    }
+
+    #[test]
+    fn test_nested_block_comments() {
+        let source = "a /* outer /* inner */ still comment */ b";
+        let tokens = Lexer::new(source).tokenize().expect("Failed to tokenize");
+        let token_types: Vec<_> = tokens.iter().map(|t| &t.token_type).collect();
+
+        assert_eq!(token_types, vec![
+            &TokenType::Identifier("a".to_string()),
+            &TokenType::Identifier("b".to_string()),
+            &TokenType::EOF,
+        ]);
+
+        let source = "let x = 1;\n/* open /* nested */\nlet y = 2;";
+        let (tokens, diagnostics) = Lexer::new(source).tokenize_recovering();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].kind, LexErrorKind::UnterminatedBlockComment);
+        assert_eq!(&source[diagnostics[0].span.lo..diagnostics[0].span.hi], "/*");
+        assert_eq!(diagnostics[0].span.lo, 11);
+        assert_eq!(tokens.last().unwrap().token_type, TokenType::EOF);
+    }
+
+    #[test]
+    fn test_relex_matches_full_lex() {
+        let source = "fn main() -> void {\n    let s = \"a\\qb\"; // note\n    let x = 1 + 2;\n    /* c */ let y = x;\n}\n";
+        let edits = [
+            TextEdit::new(60..61, "42"),
+            TextEdit::new(0..2, "fn"),
+            TextEdit::new(66..66, "\n\n    let z = 'q';"),
+            TextEdit::new(76..78, ""),
+            TextEdit::new(32..33, ""),
+            TextEdit::new(source.len()..source.len(), "// end"),
+        ];
+
+        let (tokens, diagnostics) = Lexer::new(source).tokenize_recovering();
+        for edit in &edits {
+            let new_source = edit.apply(source);
+            let relexed = relex(&tokens, &diagnostics, edit, &new_source);
+            let (expected_tokens, expected_diagnostics) = Lexer::new(&new_source).tokenize_recovering();
+
+            assert_eq!(relexed.tokens, expected_tokens, "edit {:?}", edit);
+            assert_eq!(relexed.diagnostics, expected_diagnostics, "edit {:?}", edit);
+        }
+
+        // an edit on the last line leaves everything before it alone
+        let edit = &edits[2];
+        let new_source = edit.apply(source);
+        let relexed = relex(&tokens, &diagnostics, edit, &new_source);
+        assert_eq!(relexed.unchanged_prefix, 11);
+        assert_eq!(relexed.unchanged_suffix, (19, 24));
+    }
+
+    #[test]
+    fn test_columns_are_one_based_on_every_line() {
+        let source = "a \"two\nlines\" b\n  /* x\n */ c r\"\n\" d\n\t\"\"\"\n\tz\n\t\"\"\" e";
+        let mut source_map = SourceMap::new();
+        let file_id = source_map.add_file("columns.hydra", source);
+        let tokens = Lexer::with_file_id(source_map.source(file_id), file_id).tokenize().expect("Failed to tokenize");
+
+        for token in &tokens {
+            let location = source_map.lookup(token.span).0;
+            assert_eq!((token.line, token.column), (location.line, location.column), "{:?}", token.lexeme);
+        }
+
+        let positions: Vec<_> = tokens.iter().map(|t| (t.line, t.column)).collect();
+        assert_eq!(positions, vec![(1, 1), (1, 3), (2, 8), (4, 5), (4, 7), (5, 3), (6, 2), (8, 6), (8, 7)]);
+    }
 }
@@ -1,11 +1,13 @@
+use diagnostics::Span;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum TokenType {
     // -----------------------------------------------------------------------
     // Literals
     // -----------------------------------------------------------------------
-    IntLiteral(i64),
-    FloatLiteral(f64),
-    StringLiteral(String),
+    IntLiteral { value: u128, suffix: Option<NumericSuffix> },     // 255u8
+    FloatLiteral { value: f64, suffix: Option<NumericSuffix> },     // 2.5f32
+    StringLiteral(Vec<u8>),                                        // bytes, which `\xNN` need not keep UTF-8
     CharLiteral(char),
     BoolLiteral(bool),
 
@@ -131,13 +133,135 @@ pub enum TokenType {
     // -----------------------------------------------------------------------
     // Special
     // -----------------------------------------------------------------------
+    Error,              // malformed input, reported as a LexDiagnostic
     EOF,
 }
 
+impl TokenType {
+    /// The binary operator a compound assignment applies, such as `+` for `+=`.
+    pub fn compound_operator(&self) -> Option<TokenType> {
+        Some(match self {
+            TokenType::PlusEqual => TokenType::Plus,
+            TokenType::MinusEqual => TokenType::Minus,
+            TokenType::StarEqual => TokenType::Star,
+            TokenType::ForwardSlashEqual => TokenType::ForwardSlash,
+            TokenType::ModuloEqual => TokenType::Modulo,
+            TokenType::AmpersandEqual => TokenType::Ampersand,
+            TokenType::PipeEqual => TokenType::Pipe,
+            TokenType::CarrotEqual => TokenType::Carrot,
+            TokenType::DoubleLeftEqual => TokenType::DoubleLeftAngle,
+            TokenType::DoubleRightEqual => TokenType::DoubleRightAngle,
+            _ => return None,
+        })
+    }
+
+    /// Whether this is `=` or one of the compound assignment operators.
+    pub fn is_assignment(&self) -> bool {
+        *self == TokenType::Equal || self.compound_operator().is_some()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Token<'a> {
     pub token_type: TokenType,
     pub lexeme: &'a str,
+    pub span: Span,
+    /// 1-based line of the token's first character.
     pub line: usize,
+    /// 1-based column of the token's first character, counted in `char`s.
+    /// [`diagnostics::LineIndex`] converts it to other encodings.
     pub column: usize,
 }
+
+/// Source text between tokens that carries no meaning for the parser.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriviaKind {
+    Whitespace,
+    LineComment,        // // ...
+    BlockComment,       // /* ... */
+    DocLineComment,     // /// ...
+    DocBlockComment,    // /** ... */
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trivia<'a> {
+    pub kind: TriviaKind,
+    pub text: &'a str,
+    pub span: Span,
+}
+
+/// A token with the whitespace and comments directly in front of it, as
+/// produced by [`Lexer::tokenize_lossless`](crate::Lexer::tokenize_lossless).
+#[derive(Debug, Clone, PartialEq)]
+pub struct LosslessToken<'a> {
+    pub leading: Vec<Trivia<'a>>,
+    pub token: Token<'a>,
+}
+
+impl LosslessToken<'_> {
+    /// The exact source text of the trivia and the token, in order.
+    pub fn text(&self) -> String {
+        let mut text: String = self.leading.iter().map(|t| t.text).collect();
+        text.push_str(self.token.lexeme);
+
+        text
+    }
+}
+
+/// The type suffix of a numeric literal, such as the `u8` in `255u8`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NumericSuffix {
+    ISize,
+    I8,
+    I16,
+    I32,
+    I64,
+    USize,
+    U8,
+    U16,
+    U32,
+    U64,
+    F32,
+    F64,
+}
+
+impl NumericSuffix {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "isize" => NumericSuffix::ISize,
+            "i8" => NumericSuffix::I8,
+            "i16" => NumericSuffix::I16,
+            "i32" => NumericSuffix::I32,
+            "i64" => NumericSuffix::I64,
+            "usize" => NumericSuffix::USize,
+            "u8" => NumericSuffix::U8,
+            "u16" => NumericSuffix::U16,
+            "u32" => NumericSuffix::U32,
+            "u64" => NumericSuffix::U64,
+            "f32" => NumericSuffix::F32,
+            "f64" => NumericSuffix::F64,
+            _ => return None,
+        })
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NumericSuffix::ISize => "isize",
+            NumericSuffix::I8 => "i8",
+            NumericSuffix::I16 => "i16",
+            NumericSuffix::I32 => "i32",
+            NumericSuffix::I64 => "i64",
+            NumericSuffix::USize => "usize",
+            NumericSuffix::U8 => "u8",
+            NumericSuffix::U16 => "u16",
+            NumericSuffix::U32 => "u32",
+            NumericSuffix::U64 => "u64",
+            NumericSuffix::F32 => "f32",
+            NumericSuffix::F64 => "f64",
+        }
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, NumericSuffix::F32 | NumericSuffix::F64)
+    }
+}
@@ -1,18 +1,65 @@
-use lexer::Token;
+use diagnostics::Span;
+use lexer::{Token, TokenType};
+
+/// A type as written in the source.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeExpr<'a> {
+    /// A primitive such as `i32`, or `void`.
+    Named(Token<'a>),
+
+    Array(Box<ArrayTypeExpr<'a>>),
+}
+
+/// `[T, N]`, `[const T, N]` or, for parameters, `[T, anysize]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArrayTypeExpr<'a> {
+    pub open: Token<'a>,
+    pub const_elements: bool,
+    pub element: TypeExpr<'a>,
+    /// An integer literal or `anysize`.
+    pub size: Token<'a>,
+    pub close: Token<'a>,
+}
+
+impl<'a> TypeExpr<'a> {
+    pub fn span(&self) -> Span {
+        match self {
+            TypeExpr::Named(token) => token.span,
+            TypeExpr::Array(array) => array.open.span.to(array.close.span),
+        }
+    }
+
+    pub fn is_void(&self) -> bool {
+        matches!(self, TypeExpr::Named(token) if token.lexeme == "void")
+    }
+
+    pub fn map_tokens<'b>(&self, f: &mut impl FnMut(&Token<'a>) -> Token<'b>) -> TypeExpr<'b> {
+        match self {
+            TypeExpr::Named(token) => TypeExpr::Named(f(token)),
+            TypeExpr::Array(array) => TypeExpr::Array(Box::new(ArrayTypeExpr {
+                open: f(&array.open),
+                const_elements: array.const_elements,
+                element: array.element.map_tokens(f),
+                size: f(&array.size),
+                close: f(&array.close),
+            })),
+        }
+    }
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ASTNode<'a> {
     VariableDeclaration {
         is_const: bool,
         name: Token<'a>,
-        type_annotation: Option<Token<'a>>,
+        type_annotation: Option<TypeExpr<'a>>,
         initializer: Box<ASTNode<'a>>,
     },
 
     FunctionDeclaration {
         name: Token<'a>,
-        parameters: Vec<(Token<'a>, Token<'a>)>,
-        return_type: Token<'a>,
+        parameters: Vec<(Token<'a>, TypeExpr<'a>)>,
+        return_type: TypeExpr<'a>,
         body: Vec<ASTNode<'a>>,
     },
 
@@ -20,6 +67,81 @@ pub enum ASTNode<'a> {
         value: Box<ASTNode<'a>>,
     },
 
+    /// `target = value;` or a compound form such as `target += value;`.
+    /// `target` is a variable, index or field expression.
+    Assign {
+        target: Box<ASTNode<'a>>,
+        op: Token<'a>,
+        value: Box<ASTNode<'a>>,
+    },
+
+    /// `{ statements; value }`. The block evaluates to `value`, or to
+    /// nothing when the last expression is followed by a semicolon.
+    Block {
+        open: Token<'a>,
+        statements: Vec<ASTNode<'a>>,
+        value: Option<Box<ASTNode<'a>>>,
+        close: Token<'a>,
+    },
+
+    /// `if (condition) { ... } else ...`. `else_branch` is a block or, for
+    /// `else if`, another `If`.
+    If {
+        keyword: Token<'a>,
+        condition: Box<ASTNode<'a>>,
+        then_branch: Box<ASTNode<'a>>,
+        else_branch: Option<Box<ASTNode<'a>>>,
+    },
+
+    While {
+        keyword: Token<'a>,
+        condition: Box<ASTNode<'a>>,
+        body: Box<ASTNode<'a>>,
+    },
+
+    /// `for (variable in start..end) { body }`, or `..=` when `inclusive`.
+    /// The loop counts down when `start` is greater than `end`.
+    For {
+        keyword: Token<'a>,
+        variable: Token<'a>,
+        start: Box<ASTNode<'a>>,
+        end: Box<ASTNode<'a>>,
+        inclusive: bool,
+        body: Box<ASTNode<'a>>,
+    },
+
+    /// `foreach (variable in collection) { body }` over a value whose type
+    /// takes part in the iteration protocol (see `Type::element_type`).
+    ForEach {
+        keyword: Token<'a>,
+        variable: Token<'a>,
+        collection: Box<ASTNode<'a>>,
+        body: Box<ASTNode<'a>>,
+    },
+
+    /// `break;` or `break if (condition) { body };`. The body runs right
+    /// before leaving the loop.
+    Break {
+        keyword: Token<'a>,
+        condition: Option<Box<ASTNode<'a>>>,
+        body: Option<Box<ASTNode<'a>>>,
+    },
+
+    /// `continue;` or `continue if (condition) { body };`, like `Break`.
+    Continue {
+        keyword: Token<'a>,
+        condition: Option<Box<ASTNode<'a>>>,
+        body: Option<Box<ASTNode<'a>>>,
+    },
+
+    /// `match (scrutinee) { pattern => value, ... }`. Arms are tried in order.
+    Match {
+        keyword: Token<'a>,
+        scrutinee: Box<ASTNode<'a>>,
+        arms: Vec<MatchArm<'a>>,
+        close: Token<'a>,
+    },
+
     VariableExpression {
         name: Token<'a>,
     },
@@ -29,6 +151,41 @@ pub enum ASTNode<'a> {
         arguments: Vec<ASTNode<'a>>,
     },
 
+    Binary {
+        left: Box<ASTNode<'a>>,
+        operator: Token<'a>,
+        right: Box<ASTNode<'a>>,
+    },
+
+    Unary {
+        operator: Token<'a>,
+        operand: Box<ASTNode<'a>>,
+    },
+
+    Cast {
+        value: Box<ASTNode<'a>>,
+        target_type: TypeExpr<'a>,
+    },
+
+    /// `{ 1, 2, 3 }`
+    ArrayLiteral {
+        open: Token<'a>,
+        elements: Vec<ASTNode<'a>>,
+        close: Token<'a>,
+    },
+
+    Index {
+        target: Box<ASTNode<'a>>,
+        index: Box<ASTNode<'a>>,
+        /// The closing `]`, so the span covers the whole expression.
+        close: Token<'a>,
+    },
+
+    Field {
+        target: Box<ASTNode<'a>>,
+        field: Token<'a>,
+    },
+
     Primtive {
         token: Token<'a>,
     },
@@ -37,3 +194,255 @@ pub enum ASTNode<'a> {
         token: Token<'a>,
     },
 }
+
+/// `pattern if (guard) => body`
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchArm<'a> {
+    pub pattern: Pattern<'a>,
+    pub guard: Option<ASTNode<'a>>,
+    /// An expression, or a block.
+    pub body: ASTNode<'a>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pattern<'a> {
+    /// `_`, which matches anything.
+    Wildcard(Token<'a>),
+
+    /// A name, which matches anything and binds the value in the arm.
+    Binding(Token<'a>),
+
+    /// An integer, `char` or `bool` literal. Integers may be negated, in
+    /// which case this is a `Unary`.
+    Literal(Box<ASTNode<'a>>),
+
+    /// `start..=end` over integer or `char` literals.
+    Range {
+        start: Box<ASTNode<'a>>,
+        end: Box<ASTNode<'a>>,
+    },
+}
+
+impl<'a> Pattern<'a> {
+    pub fn span(&self) -> Span {
+        match self {
+            Pattern::Wildcard(token) | Pattern::Binding(token) => token.span,
+            Pattern::Literal(value) => value.span(),
+            Pattern::Range { start, end } => start.span().to(end.span()),
+        }
+    }
+
+    /// Whether the pattern matches every value, leaving nothing for later arms.
+    pub fn is_catch_all(&self) -> bool {
+        matches!(self, Pattern::Wildcard(_) | Pattern::Binding(_))
+    }
+
+    pub fn map_tokens<'b>(&self, f: &mut impl FnMut(&Token<'a>) -> Token<'b>) -> Pattern<'b> {
+        match self {
+            Pattern::Wildcard(token) => Pattern::Wildcard(f(token)),
+            Pattern::Binding(token) => Pattern::Binding(f(token)),
+            Pattern::Literal(value) => Pattern::Literal(Box::new(value.map_tokens(f))),
+            Pattern::Range { start, end } => Pattern::Range {
+                start: Box::new(start.map_tokens(f)),
+                end: Box::new(end.map_tokens(f)),
+            },
+        }
+    }
+}
+
+impl<'a> ASTNode<'a> {
+    /// The span diagnostics about this node should point at.
+    pub fn span(&self) -> Span {
+        match self {
+            ASTNode::VariableDeclaration { name, initializer, .. } => name.span.to(initializer.span()),
+            ASTNode::FunctionDeclaration { name, .. } => name.span,
+            ASTNode::ReturnStatement { value } => value.span(),
+            ASTNode::Assign { target, value, .. } => target.span().to(value.span()),
+            ASTNode::Block { open, close, .. } => open.span.to(close.span),
+            ASTNode::If { keyword, then_branch, else_branch, .. } => {
+                keyword.span.to(else_branch.as_ref().unwrap_or(then_branch).span())
+            }
+            ASTNode::While { keyword, .. } | ASTNode::For { keyword, .. } | ASTNode::ForEach { keyword, .. } => keyword.span,
+            ASTNode::Break { keyword, .. } | ASTNode::Continue { keyword, .. } => keyword.span,
+            ASTNode::Match { keyword, close, .. } => keyword.span.to(close.span),
+            ASTNode::VariableExpression { name } => name.span,
+            ASTNode::FunctionCallExpression { name, .. } => name.span,
+            ASTNode::Binary { left, right, .. } => left.span().to(right.span()),
+            ASTNode::Unary { operator, operand } => operator.span.to(operand.span()),
+            ASTNode::Cast { value, target_type } => value.span().to(target_type.span()),
+            ASTNode::ArrayLiteral { open, close, .. } => open.span.to(close.span),
+            ASTNode::Index { target, close, .. } => target.span().to(close.span),
+            ASTNode::Field { target, field } => target.span().to(field.span),
+            ASTNode::Primtive { token } => token.span,
+            ASTNode::Expression { token } => token.span,
+        }
+    }
+
+    /// Whether this is an unsuffixed numeric literal, or arithmetic on only
+    /// such literals, whose type comes from its context.
+    pub fn is_untyped_literal(&self) -> bool {
+        match self {
+            ASTNode::Expression { token } => matches!(
+                token.token_type,
+                TokenType::IntLiteral { suffix: None, .. } | TokenType::FloatLiteral { suffix: None, .. }
+            ),
+            ASTNode::Unary { operand, .. } => operand.is_untyped_literal(),
+            ASTNode::Block { value, .. } => value.as_ref().is_some_and(|value| value.is_untyped_literal()),
+            ASTNode::If { then_branch, else_branch, .. } => {
+                then_branch.is_untyped_literal() && else_branch.as_ref().is_some_and(|branch| branch.is_untyped_literal())
+            }
+            ASTNode::Match { arms, .. } => !arms.is_empty() && arms.iter().all(|arm| arm.body.is_untyped_literal()),
+            ASTNode::Binary { left, operator, right } => {
+                !matches!(
+                    operator.token_type,
+                    TokenType::DoubleAmpersand
+                        | TokenType::DoublePipe
+                        | TokenType::DoubleEqual
+                        | TokenType::ExclamEqual
+                        | TokenType::LeftAngle
+                        | TokenType::RightAngle
+                        | TokenType::LessEqual
+                        | TokenType::GreaterEqual
+                ) && left.is_untyped_literal() && right.is_untyped_literal()
+            }
+            _ => false,
+        }
+    }
+
+    /// The value of an integer, `char` or `bool` literal, possibly negated,
+    /// such as in a pattern.
+    pub fn literal_value(&self) -> Option<i128> {
+        match self {
+            ASTNode::Expression { token } => match token.token_type {
+                TokenType::IntLiteral { value, .. } => i128::try_from(value).ok(),
+                TokenType::CharLiteral(c) => Some(c as i128),
+                TokenType::BoolLiteral(b) => Some(b as i128),
+                _ => None,
+            },
+            ASTNode::Unary { operator, operand } if operator.token_type == TokenType::Minus => {
+                operand.literal_value().map(|value| -value)
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether this expression names a place that can be assigned to.
+    pub fn is_place(&self) -> bool {
+        matches!(self, ASTNode::VariableExpression { .. } | ASTNode::Index { .. } | ASTNode::Field { .. })
+    }
+
+    /// Rebuilds the node with every token passed through `f`, such as to
+    /// move a tree over to an edited copy of its source text.
+    pub fn map_tokens<'b>(&self, f: &mut impl FnMut(&Token<'a>) -> Token<'b>) -> ASTNode<'b> {
+        match self {
+            ASTNode::VariableDeclaration { is_const, name, type_annotation, initializer } => ASTNode::VariableDeclaration {
+                is_const: *is_const,
+                name: f(name),
+                type_annotation: type_annotation.as_ref().map(|ty| ty.map_tokens(f)),
+                initializer: Box::new(initializer.map_tokens(f)),
+            },
+            ASTNode::FunctionDeclaration { name, parameters, return_type, body } => ASTNode::FunctionDeclaration {
+                name: f(name),
+                parameters: parameters.iter().map(|(name, ty)| (f(name), ty.map_tokens(f))).collect(),
+                return_type: return_type.map_tokens(f),
+                body: body.iter().map(|node| node.map_tokens(f)).collect(),
+            },
+            ASTNode::ReturnStatement { value } => ASTNode::ReturnStatement {
+                value: Box::new(value.map_tokens(f)),
+            },
+            ASTNode::Assign { target, op, value } => ASTNode::Assign {
+                target: Box::new(target.map_tokens(f)),
+                op: f(op),
+                value: Box::new(value.map_tokens(f)),
+            },
+            ASTNode::Block { open, statements, value, close } => ASTNode::Block {
+                open: f(open),
+                statements: statements.iter().map(|node| node.map_tokens(f)).collect(),
+                value: value.as_ref().map(|value| Box::new(value.map_tokens(f))),
+                close: f(close),
+            },
+            ASTNode::If { keyword, condition, then_branch, else_branch } => ASTNode::If {
+                keyword: f(keyword),
+                condition: Box::new(condition.map_tokens(f)),
+                then_branch: Box::new(then_branch.map_tokens(f)),
+                else_branch: else_branch.as_ref().map(|branch| Box::new(branch.map_tokens(f))),
+            },
+            ASTNode::While { keyword, condition, body } => ASTNode::While {
+                keyword: f(keyword),
+                condition: Box::new(condition.map_tokens(f)),
+                body: Box::new(body.map_tokens(f)),
+            },
+            ASTNode::For { keyword, variable, start, end, inclusive, body } => ASTNode::For {
+                keyword: f(keyword),
+                variable: f(variable),
+                start: Box::new(start.map_tokens(f)),
+                end: Box::new(end.map_tokens(f)),
+                inclusive: *inclusive,
+                body: Box::new(body.map_tokens(f)),
+            },
+            ASTNode::ForEach { keyword, variable, collection, body } => ASTNode::ForEach {
+                keyword: f(keyword),
+                variable: f(variable),
+                collection: Box::new(collection.map_tokens(f)),
+                body: Box::new(body.map_tokens(f)),
+            },
+            ASTNode::Break { keyword, condition, body } => ASTNode::Break {
+                keyword: f(keyword),
+                condition: condition.as_ref().map(|condition| Box::new(condition.map_tokens(f))),
+                body: body.as_ref().map(|body| Box::new(body.map_tokens(f))),
+            },
+            ASTNode::Continue { keyword, condition, body } => ASTNode::Continue {
+                keyword: f(keyword),
+                condition: condition.as_ref().map(|condition| Box::new(condition.map_tokens(f))),
+                body: body.as_ref().map(|body| Box::new(body.map_tokens(f))),
+            },
+            ASTNode::Match { keyword, scrutinee, arms, close } => ASTNode::Match {
+                keyword: f(keyword),
+                scrutinee: Box::new(scrutinee.map_tokens(f)),
+                arms: arms
+                    .iter()
+                    .map(|arm| MatchArm {
+                        pattern: arm.pattern.map_tokens(f),
+                        guard: arm.guard.as_ref().map(|guard| guard.map_tokens(f)),
+                        body: arm.body.map_tokens(f),
+                    })
+                    .collect(),
+                close: f(close),
+            },
+            ASTNode::VariableExpression { name } => ASTNode::VariableExpression { name: f(name) },
+            ASTNode::FunctionCallExpression { name, arguments } => ASTNode::FunctionCallExpression {
+                name: f(name),
+                arguments: arguments.iter().map(|node| node.map_tokens(f)).collect(),
+            },
+            ASTNode::Binary { left, operator, right } => ASTNode::Binary {
+                left: Box::new(left.map_tokens(f)),
+                operator: f(operator),
+                right: Box::new(right.map_tokens(f)),
+            },
+            ASTNode::Unary { operator, operand } => ASTNode::Unary {
+                operator: f(operator),
+                operand: Box::new(operand.map_tokens(f)),
+            },
+            ASTNode::Cast { value, target_type } => ASTNode::Cast {
+                value: Box::new(value.map_tokens(f)),
+                target_type: target_type.map_tokens(f),
+            },
+            ASTNode::ArrayLiteral { open, elements, close } => ASTNode::ArrayLiteral {
+                open: f(open),
+                elements: elements.iter().map(|node| node.map_tokens(f)).collect(),
+                close: f(close),
+            },
+            ASTNode::Index { target, index, close } => ASTNode::Index {
+                target: Box::new(target.map_tokens(f)),
+                index: Box::new(index.map_tokens(f)),
+                close: f(close),
+            },
+            ASTNode::Field { target, field } => ASTNode::Field {
+                target: Box::new(target.map_tokens(f)),
+                field: f(field),
+            },
+            ASTNode::Primtive { token } => ASTNode::Primtive { token: f(token) },
+            ASTNode::Expression { token } => ASTNode::Expression { token: f(token) },
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use diagnostics::{Diagnostic, FileId};
+use lexer::{relex, LexDiagnostic, Lexer, TextEdit, Token};
+
+use crate::ast::ASTNode;
+use crate::parser::Parser;
+
+/// The tokens and tree of one version of a source file, kept so that the
+/// next version can be lexed and parsed incrementally with
+/// [`ParsedFile::reparse`].
+#[derive(Debug, Clone)]
+pub struct ParsedFile<'a> {
+    tokens: Vec<Token<'a>>,
+    lex_errors: Vec<LexDiagnostic>,
+    items: Vec<ASTNode<'a>>,
+    ranges: Vec<Range<usize>>,
+    error: Option<Diagnostic>,
+}
+
+impl<'a> ParsedFile<'a> {
+    /// Lexes and parses `text` from scratch.
+    pub fn parse(text: &'a str, file_id: FileId) -> Self {
+        let (tokens, lex_errors) = Lexer::with_file_id(text, file_id).tokenize_recovering();
+
+        Self::parse_tokens(tokens, lex_errors, |_| None)
+    }
+
+    /// Lexes and parses `text`, the result of applying `edit` to the text of
+    /// `self`. Only the tokens around the edit are scanned again, and top-level
+    /// declarations whose tokens did not change are reused rather than parsed.
+    /// The result is identical to calling [`ParsedFile::parse`] on `text`.
+    pub fn reparse<'b>(&self, edit: &TextEdit, text: &'b str) -> ParsedFile<'b> {
+        let relexed = relex(&self.tokens, &self.lex_errors, edit, text);
+        let (old_suffix, new_suffix) = relexed.unchanged_suffix;
+        let shift = relexed.shift;
+
+        // A declaration is only reusable if its tokens and the one after it,
+        // which the parser may have looked at, are unchanged.
+        let mut reusable = HashMap::new();
+        for (index, range) in self.ranges.iter().enumerate() {
+            if range.end < relexed.unchanged_prefix {
+                reusable.insert(range.start, (index, range.end));
+            } else if range.start >= old_suffix {
+                reusable.insert(range.start - old_suffix + new_suffix, (index, range.end - old_suffix + new_suffix));
+            }
+        }
+
+        ParsedFile::parse_tokens(relexed.tokens, relexed.diagnostics, |start| {
+            reusable
+                .get(&start)
+                .map(|&(index, end)| (self.items[index].map_tokens(&mut |token| shift.apply(token)), end))
+        })
+    }
+
+    fn parse_tokens(
+        tokens: Vec<Token<'a>>,
+        lex_errors: Vec<LexDiagnostic>,
+        reuse: impl FnMut(usize) -> Option<(ASTNode<'a>, usize)>,
+    ) -> Self {
+        let mut parser = Parser::new(tokens);
+        let mut items = Vec::new();
+        let mut ranges = Vec::new();
+
+        let error = parser
+            .parse_items(&mut items, &mut ranges, reuse)
+            .and_then(|()| parser.main_function_exists(&items))
+            .err()
+            .map(|error| *error);
+
+        Self {
+            tokens: parser.into_tokens(),
+            lex_errors,
+            items,
+            ranges,
+            error,
+        }
+    }
+
+    pub fn tokens(&self) -> &[Token<'a>] {
+        &self.tokens
+    }
+
+    pub fn lex_errors(&self) -> &[LexDiagnostic] {
+        &self.lex_errors
+    }
+
+    /// The tree, or the parse error that stopped parsing, as returned by
+    /// [`Parser::parse`].
+    pub fn ast(&self) -> Result<&[ASTNode<'a>], &Diagnostic> {
+        match &self.error {
+            Some(error) => Err(error),
+            None => Ok(&self.items),
+        }
+    }
+}
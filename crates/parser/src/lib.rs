@@ -1,6 +1,480 @@
 pub mod ast;
+pub mod incremental;
 pub mod parser;
 pub mod semantic;
 pub mod type_check;
+pub mod types;
 
-pub use ast::*;
\ No newline at end of file
+pub use ast::*;
+
+#[cfg(test)]
+mod tests {
+    use diagnostics::{codes, FileId};
+    use lexer::{Lexer, TextEdit};
+
+    use crate::ast::{ASTNode, TypeExpr};
+    use crate::incremental::ParsedFile;
+    use crate::parser::Parser;
+    use crate::semantic::SemanticChecker;
+    use crate::type_check::TypeChecker;
+
+    fn type_check(source: &str) -> Result<(), Box<diagnostics::Diagnostic>> {
+        let tokens = Lexer::new(source).tokenize().expect("Failed to tokenize");
+        let ast = Parser::new(tokens).parse().expect("Failed to parse");
+
+        TypeChecker::new().check(&ast).map_err(|mut errors| Box::new(errors.remove(0)))
+    }
+
+    /// Runs the type and semantic checkers, returning the warnings.
+    fn semantic_check(source: &str) -> Result<Vec<diagnostics::Diagnostic>, Box<diagnostics::Diagnostic>> {
+        let tokens = Lexer::new(source).tokenize().expect("Failed to tokenize");
+        let ast = Parser::new(tokens).parse().expect("Failed to parse");
+
+        let mut type_checker = TypeChecker::new();
+        type_checker.check(&ast).map_err(|mut errors| Box::new(errors.remove(0)))?;
+        let mut checker = SemanticChecker::new(type_checker.match_types());
+        checker.check(&ast)?;
+
+        Ok(checker.warnings().to_vec())
+    }
+
+    #[test]
+    fn test_parse_error_is_structured() {
+        let source = "fn main() -> void {\n    let x = 1\n}";
+        let tokens = Lexer::new(source).tokenize().expect("Failed to tokenize");
+        let error = Parser::new(tokens).parse().expect_err("Expected a parse error");
+
+        assert_eq!(error.code, codes::EXPECTED_TOKEN);
+        assert_eq!(error.message, "expected ';' at the end of line");
+        assert_eq!(&source[error.primary_span.unwrap().lo..], "}");
+        assert_eq!(error.suggestions[0].span.lo, source.find("1\n").unwrap() + 1);
+    }
+
+    #[test]
+    fn test_literal_range_checks() {
+        assert!(type_check("fn main() -> void { let a: u8 = 255; let b = 0xFFFF_FFFF_FFFF_FFFFu64; }").is_ok());
+
+        let error = type_check("fn main() -> void { let a: u8 = 256; }").unwrap_err();
+        assert_eq!(error.code, codes::LITERAL_OUT_OF_RANGE);
+        assert_eq!(error.message, "literal out of range for `u8`");
+
+        let error = type_check("fn main() -> void { let a = 2147483648; }").unwrap_err();
+        assert_eq!(error.message, "literal out of range for `i32`");
+
+        let error = type_check("fn f() -> u8 { return 1i64; } fn main() -> void { }").unwrap_err();
+        assert_eq!(error.code, codes::MISMATCHED_TYPES);
+    }
+
+    /// Parses `expression` and prints it with every operator parenthesized.
+    fn grouping(expression: &str) -> String {
+        fn print(node: &ASTNode) -> String {
+            match node {
+                ASTNode::Binary { left, operator, right } => format!("({} {} {})", print(left), operator.lexeme, print(right)),
+                ASTNode::Unary { operator, operand } => format!("({}{})", operator.lexeme, print(operand)),
+                ASTNode::Cast { value, target_type: TypeExpr::Named(target_type) } => {
+                    format!("({} as {})", print(value), target_type.lexeme)
+                }
+                ASTNode::Index { target, index, .. } => format!("{}[{}]", print(target), print(index)),
+                ASTNode::Field { target, field } => format!("{}.{}", print(target), field.lexeme),
+                ASTNode::Expression { token } => token.lexeme.to_string(),
+                ASTNode::VariableExpression { name } => name.lexeme.to_string(),
+                other => panic!("unexpected node {:?}", other),
+            }
+        }
+
+        let source = format!("fn main() -> void {{ let x = {}; }}", expression);
+        let tokens = Lexer::new(&source).tokenize().expect("Failed to tokenize");
+        let ast = Parser::new(tokens).parse().expect("Failed to parse");
+
+        match &ast[0] {
+            ASTNode::FunctionDeclaration { body, .. } => match &body[0] {
+                ASTNode::VariableDeclaration { initializer, .. } => print(initializer),
+                other => panic!("unexpected node {:?}", other),
+            },
+            other => panic!("unexpected node {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_operator_precedence() {
+        assert_eq!(grouping("a + b * c - d"), "((a + (b * c)) - d)");
+        assert_eq!(grouping("a || b && c == d"), "(a || (b && (c == d)))");
+        assert_eq!(grouping("a & b ^ c | d << 1"), "(((a & b) ^ c) | (d << 1))");
+        assert_eq!(grouping("-a * !b as i64"), "((-a) * ((!b) as i64))");
+        assert_eq!(grouping("(a < b) == c"), "((a < b) == c)");
+
+        let source = "fn main() -> void { let x = a < b < c; }";
+        let tokens = Lexer::new(source).tokenize().expect("Failed to tokenize");
+        let error = Parser::new(tokens).parse().expect_err("Expected a parse error");
+        assert_eq!(error.code, codes::CHAINED_COMPARISON);
+        assert_eq!(&source[error.primary_span.unwrap().lo..][..1], "<");
+    }
+
+    #[test]
+    fn test_operator_type_checks() {
+        assert!(type_check("fn main() -> void { let a: u8 = 1; let b = a << 3i64 | 1; let c = -128i8; }").is_ok());
+        assert!(type_check("fn main() -> void { let a = 2.5; let b: bool = a as i32 > 2 && !false; }").is_ok());
+
+        let error = type_check("fn main() -> void { let a = -129i8; }").unwrap_err();
+        assert_eq!(error.code, codes::LITERAL_OUT_OF_RANGE);
+
+        let error = type_check("fn main() -> void { let a: u32 = 1; let b = -a; }").unwrap_err();
+        assert_eq!(error.code, codes::INVALID_OPERAND);
+        assert_eq!(error.message, "cannot apply unary operator `-` to type `u32`");
+
+        let error = type_check("fn main() -> void { let a = 1.0 << 2; }").unwrap_err();
+        assert_eq!(error.message, "binary operation `<<` cannot be applied to type `f64`");
+
+        let error = type_check("fn main() -> void { let a = 1i64 + 2i32; }").unwrap_err();
+        assert_eq!(error.code, codes::MISMATCHED_TYPES);
+
+        let error = type_check("fn main() -> void { let a = 300 as char; }").unwrap_err();
+        assert_eq!(error.code, codes::INVALID_CAST);
+        assert_eq!(error.message, "casting `i32` as `char` is invalid");
+    }
+
+    #[test]
+    fn test_assignments() {
+        assert!(type_check("fn main() -> void { let x: u8 = 1; x = 2; x += 3; x <<= 1i64; let b = true; b |= x > 2; }").is_ok());
+
+        let source = "fn main() -> void { const x = 1; x -= 1; }";
+        let error = type_check(source).unwrap_err();
+        assert_eq!(error.code, codes::ASSIGN_TO_CONST);
+        assert_eq!(error.message, "cannot assign to `x`, which is declared `const`");
+        assert_eq!(error.labels[0].span.lo, source.find("x =").unwrap());
+
+        let error = type_check("fn main() -> void { let x = 1.5; x %= 2; }").unwrap_err();
+        assert_eq!(error.code, codes::MISMATCHED_TYPES);
+
+        let error = type_check("fn main() -> void { let x = 1.5; x ^= 2.0; }").unwrap_err();
+        assert_eq!(error.message, "binary operation `^=` cannot be applied to type `f64`");
+
+        let source = "fn main() -> void { let x = 1; x + 1 = 2; }";
+        let tokens = Lexer::new(source).tokenize().expect("Failed to tokenize");
+        let error = Parser::new(tokens).parse().expect_err("Expected a parse error");
+        assert_eq!(error.code, codes::INVALID_ASSIGNMENT_TARGET);
+        assert_eq!(grouping("a[i + 1].len"), "a[(i + 1)].len");
+    }
+
+    #[test]
+    fn test_if_statements_and_expressions() {
+        let source = "fn main() -> void {
+            let n = 3;
+            if (n > 2) { let m = n; m += 1; } else if (n == 0) { println(\"zero\"); } else { }
+            let a: u8 = if (n < 0) { 1 } else if (n == 0) { 2 } else { let b = 3u8; b };
+            let c = if (true) { 1 } else { 2i64 } + 3i64;
+        }";
+        assert!(type_check(source).is_ok());
+
+        let error = type_check("fn main() -> void { let n = 1; if (n) { } }").unwrap_err();
+        assert_eq!(error.code, codes::MISMATCHED_TYPES);
+        assert_eq!(error.notes[0], "expected `bool`, found `i32`");
+
+        let error = type_check("fn main() -> void { let a = if (true) { 1 }; }").unwrap_err();
+        assert_eq!(error.code, codes::IF_WITHOUT_ELSE);
+
+        let error = type_check("fn main() -> void { let a = if (true) { 1 } else { 'x' }; }").unwrap_err();
+        assert_eq!(error.code, codes::MISMATCHED_TYPES);
+
+        // variables declared in a block go out of scope with it
+        let error = type_check("fn main() -> void { if (true) { let m = 1; } let n = m; }").unwrap_err();
+        assert_eq!(error.code, codes::UNDEFINED_VARIABLE);
+    }
+
+    #[test]
+    fn test_missing_return() {
+        let source = "fn sign(x: i32) -> i32 {
+            if (x > 0) { return 1; } else if (x < 0) { return -1; } else { return 0; }
+        }
+
+        fn parity(x: u8) -> bool {
+            match (x % 2) { 0 => { return true; } _ => { return false; } }
+        }
+
+        fn first_even(x: i32) -> i32 {
+            while (true) {
+                for (i in 0..3) { break if (i == x); }
+                if (x % 2 == 0) { return x; }
+                x += 1;
+            }
+        }
+
+        fn main() -> void {}";
+        assert!(semantic_check(source).is_ok());
+
+        let error = semantic_check("fn f(x: i32) -> i32 { if (x > 0) { return 1; } } fn main() -> void {}").unwrap_err();
+        assert_eq!(error.code, codes::MISSING_RETURN);
+        assert_eq!(error.message, "not all paths return a value");
+
+        let source = "fn f(x: i32) -> i32 { while (true) { break if (x > 0); return 1; } } fn main() -> void {}";
+        assert_eq!(semantic_check(source).unwrap_err().code, codes::MISSING_RETURN);
+
+        let source = "fn f(x: i32) -> i32 { for (i in 0..x) { return i; } } fn main() -> void {}";
+        assert_eq!(semantic_check(source).unwrap_err().code, codes::MISSING_RETURN);
+    }
+
+    #[test]
+    fn test_loop_control() {
+        let source = "fn main() -> void {
+            let i = 0;
+            while (i < 10) {
+                i += 1;
+                continue if (i == 2);
+                break if (i > 5) { println(\"{}\", i); };
+                if (i == 4) { break; }
+            }
+        }";
+        assert!(semantic_check(source).is_ok());
+
+        let source = "fn main() -> void { let i = 0; if (i == 0) { continue if (true) { i = 1; }; } }";
+        let error = semantic_check(source).unwrap_err();
+        assert_eq!(error.code, codes::OUTSIDE_LOOP);
+        assert_eq!(error.message, "`continue` outside of a loop");
+
+        let error = type_check("fn main() -> void { while (1) { } }").unwrap_err();
+        assert_eq!(error.code, codes::MISMATCHED_TYPES);
+    }
+
+    #[test]
+    fn test_range_for_loops() {
+        let source = "fn main() -> void {
+            let n: u8 = 5;
+            for (i in n..=0) { let m: u8 = i; continue if (m == 2); }
+            for (i in 10..-10) { let m = i; }
+        }";
+        assert!(semantic_check(source).is_ok());
+
+        let error = type_check("fn main() -> void { for (i in 0.5..2.5) { } }").unwrap_err();
+        assert_eq!(error.code, codes::INVALID_RANGE);
+        assert_eq!(error.message, "range bounds must be integers, found `f64`");
+
+        let error = type_check("fn main() -> void { for (i in 0u8..10i64) { } }").unwrap_err();
+        assert_eq!(error.code, codes::MISMATCHED_TYPES);
+
+        let error = type_check("fn main() -> void { for (i in 0..10) { i += 1; } }").unwrap_err();
+        assert_eq!(error.code, codes::ASSIGN_TO_CONST);
+
+        let error = type_check("fn main() -> void { for (i in 0..10) { } let j = i; }").unwrap_err();
+        assert_eq!(error.code, codes::UNDEFINED_VARIABLE);
+    }
+
+    #[test]
+    fn test_foreach() {
+        let source = "fn sum(numbers: [i32, anysize]) -> i32 {
+            let total = 0;
+            foreach (n in numbers) { total += n; }
+            return total;
+        }
+
+        fn main() -> void {
+            let a: [i32, 3] = { 1, 2, 3 };
+            const b: [const char, 2] = { 'x', 'y' };
+            let c = sum(a) + sum({ 4, 5 });
+            foreach (letter in b) { let l: char = letter; break if (l == 'x'); }
+            foreach (f in { 1.5, 2.5f32 }) { let g: f32 = f; }
+        }";
+        assert!(semantic_check(source).is_ok());
+
+        let error = type_check("fn main() -> void { let n = 3; foreach (i in n) { } }").unwrap_err();
+        assert_eq!(error.code, codes::NOT_ITERABLE);
+        assert_eq!(error.message, "`i32` is not iterable");
+    }
+
+    #[test]
+    fn test_array_indexing() {
+        let source = "fn main() -> void {
+            let a: [i32, 3] = { 1, 2, 3 };
+            let i: u8 = 2;
+            a[0] = a[i] * 2;
+            a[1] += 1;
+            let grid: [[i64, 2], 2] = { { 1, 2 }, { 3, 4 } };
+            grid[1][0] = grid[0][1];
+            let c: char = { 'a', 'b' }[1];
+        }";
+        assert!(type_check(source).is_ok());
+
+        let source = "fn first(numbers: [i32, anysize]) -> i32 {
+            return numbers[0];
+        }
+
+        fn main() -> void {
+            let a: [i32, 3] = { 1, 2, 3 };
+            const b: [const char, 2] = { 'x', 'y' };
+            let c = first(a) + first({ 4, 5 });
+        }";
+        assert!(type_check(source).is_ok());
+
+        let error = type_check("fn main() -> void { let a: [i32, anysize] = { 1 }; }").unwrap_err();
+        assert_eq!(error.code, codes::ANYSIZE_NOT_ALLOWED);
+
+        let error = type_check("fn main() -> void { let a: [i32, 2] = { 1, 2, 3 }; }").unwrap_err();
+        assert_eq!(error.notes[0], "expected `[i32, 2]`, found `[i32, 3]`");
+
+        let error = type_check("fn main() -> void { let a = {}; }").unwrap_err();
+        assert_eq!(error.code, codes::TYPE_ANNOTATIONS_NEEDED);
+
+        let error = type_check("fn f(grid: [[u8, 4294967296], 2]) -> void {} fn main() -> void {}").unwrap_err();
+        assert_eq!(error.code, codes::ARRAY_TOO_LARGE);
+        assert_eq!(error.message, "array size 4294967296 is too large");
+
+        let error = type_check("fn main() -> void { let a = { 1, 2 }; let b = a[1.5]; }").unwrap_err();
+        assert_eq!(error.code, codes::INVALID_INDEX);
+        assert_eq!(error.message, "array index must be an integer, found `f64`");
+
+        let error = type_check("fn main() -> void { let a = 3; let b = a[0]; }").unwrap_err();
+        assert_eq!(error.code, codes::NOT_INDEXABLE);
+
+        let error = type_check("fn main() -> void { let a: [bool, 2] = { true, false }; a[1] = 3; }").unwrap_err();
+        assert_eq!(error.code, codes::MISMATCHED_TYPES);
+    }
+
+    #[test]
+    fn test_const_elements() {
+        let source = "fn fill(a: [i32, anysize]) -> void { a[0] = 1; }
+        fn first(a: [const i32, anysize]) -> i32 { return a[0]; }
+
+        fn main() -> void {
+            const c: [i32, 2] = { 1, 2 };
+            c[0] = 4;
+            fill(c);
+            let b: [const i32, 2] = { 1, 2 };
+            b = { 3, 4 };
+            let copy: [i32, 2] = b;
+            copy[1] = first(b) + first(copy);
+        }";
+        assert!(type_check(source).is_ok());
+
+        let source = "fn main() -> void {
+            const d: [const i32, 2] = { 1, 2 };
+            d[1] = 2;
+            d = { 3, 4 };
+            let g: [const [i32, 2], 2] = { { 1, 2 }, { 3, 4 } };
+            g[1][0] += 3;
+        }";
+        let tokens = Lexer::new(source).tokenize().expect("Failed to tokenize");
+        let ast = Parser::new(tokens).parse().expect("Failed to parse");
+        let errors = TypeChecker::new().check(&ast).unwrap_err();
+        let found: Vec<_> = errors.iter().map(|error| error.code).collect();
+        assert_eq!(found, [codes::ASSIGN_TO_CONST_ELEMENT, codes::ASSIGN_TO_CONST, codes::ASSIGN_TO_CONST_ELEMENT]);
+        assert_eq!(errors[0].message, "cannot assign to an element of `d`, whose elements are `const`");
+        assert_eq!(&source[errors[2].primary_span.unwrap().lo..][..8], "g[1][0] ");
+
+        let error = type_check("fn f(a: [i32, anysize]) -> void { } fn main() -> void { let b: [const i32, 1] = { 1 }; f(b); }").unwrap_err();
+        assert_eq!(error.code, codes::CONST_ELEMENTS_DISCARDED);
+        assert_eq!(error.message, "cannot use `[const i32, 1]` as `[i32, anysize]`, which allows changing its elements");
+
+        let source = "fn f(a: [i32, anysize]) -> void { a[0] = 1; a = { 1, 2 }; const b = a; } fn main() -> void { }";
+        let errors = TypeChecker::new().check(&Parser::new(Lexer::new(source).tokenize().unwrap()).parse().unwrap()).unwrap_err();
+        let found: Vec<_> = errors.iter().map(|error| error.code).collect();
+        assert_eq!(found, [codes::ASSIGN_TO_ANYSIZE, codes::ANYSIZE_NOT_ALLOWED]);
+        assert_eq!(errors[0].message, "cannot assign to `a`, which is an `anysize` array");
+        assert_eq!(errors[1].message, "cannot store `[i32, anysize]` in a variable");
+    }
+
+    #[test]
+    fn test_match_expressions() {
+        let source = "fn main() -> void {
+            let x: i64 = 7;
+            let sign = match (x) { 0 => 0, n if (n < 0) => -1, _ => 1 };
+            let digit: u8 = match ('7') { '0'..='9' => 1, c => { let d: char = c; 0 } };
+            match (x % 3) {
+                -2..=-1 => { continue_counting(); }
+                0 => continue_counting(),
+                _ => {}
+            }
+        }
+
+        fn continue_counting() -> void {}";
+        assert!(semantic_check(source).is_ok());
+
+        let error = type_check("fn main() -> void { let y = match (1) { 0 => true, _ => 3 }; }").unwrap_err();
+        assert_eq!(error.code, codes::MISMATCHED_TYPES);
+        assert_eq!(error.notes[0], "expected `bool`, found `i32`");
+
+        let error = type_check("fn main() -> void { let y = match (1u8) { 5..=2 => 1, _ => 2 }; }").unwrap_err();
+        assert_eq!(error.code, codes::INVALID_RANGE);
+
+        let error = type_check("fn main() -> void { let y = match (1) { n if (n) => 1, _ => 2 }; }").unwrap_err();
+        assert_eq!(error.code, codes::MISMATCHED_TYPES);
+
+        let error = type_check("fn main() -> void { let y = match (1) { 0 => 1, _ => n }; }").unwrap_err();
+        assert_eq!(error.code, codes::UNDEFINED_VARIABLE);
+
+        // the arm the others take their type from is only checked once
+        let source = "fn main() -> void { const c = 1; let y = match (1) { 0 => 2, _ => { c = 3; 4u8 } }; }";
+        let errors = TypeChecker::new().check(&Parser::new(Lexer::new(source).tokenize().unwrap()).parse().unwrap()).unwrap_err();
+        let found: Vec<_> = errors.iter().map(|error| error.code).collect();
+        assert_eq!(found, [codes::ASSIGN_TO_CONST]);
+
+        let tokens = Lexer::new("fn main() -> void { let y = match (1) { 0..3 => 1 }; }").tokenize().unwrap();
+        assert_eq!(Parser::new(tokens).parse().unwrap_err().code, codes::EXPECTED_PATTERN);
+    }
+
+    #[test]
+    fn test_match_exhaustiveness() {
+        let source = "fn main() -> void {
+            let x: u8 = 7;
+            let a = match (x) { 0 => 1, 1..=254 => 2, 255 => 3 };
+            let b = match (x > 3) { true => 1, false => 0 };
+            let c = match ('q') { '\\0'..='z' => 1, other => 2 };
+            let d = match (2.5) { f => f };
+        }";
+        assert_eq!(semantic_check(source), Ok(vec![]));
+
+        let error = semantic_check("fn main() -> void { let x = 3; let s = match (x % 2) { 0 => 1, 1 => 2 }; }").unwrap_err();
+        assert_eq!(error.code, codes::NON_EXHAUSTIVE_MATCH);
+        assert_eq!(error.message, "non-exhaustive patterns: `i32::MIN..=-1` and `2..=i32::MAX` not covered");
+
+        let error = semantic_check("fn main() -> void { match (true) { true => {} } }").unwrap_err();
+        assert_eq!(error.message, "non-exhaustive patterns: `false` not covered");
+
+        let error = semantic_check("fn main() -> void { let x = 1u8; match (x) { 0 => {} n if (n > 0) => {} } }").unwrap_err();
+        assert_eq!(error.message, "non-exhaustive patterns: `1..=u8::MAX` not covered");
+
+        let error = semantic_check("fn main() -> void { match (1u8) { 1 => {} 3 => {} 5 => {} 7 => {} } }").unwrap_err();
+        assert_eq!(error.message, "non-exhaustive patterns: `0`, `2`, `4` and 2 more not covered");
+
+        let source = "fn main() -> void { let x = 1u8; match (x) { 0..=9 => {} 5 => {} _ => {} n => {} } }";
+        let warnings = semantic_check(source).unwrap();
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(warnings[0].code, codes::UNREACHABLE_PATTERN);
+        assert_eq!(&source[warnings[0].primary_span.unwrap().lo..][..1], "5");
+        assert_eq!(&source[warnings[1].primary_span.unwrap().lo..][..1], "n");
+    }
+
+    #[test]
+    fn test_incremental_reparse_matches_full_parse() {
+        let source = "const A: i32 = 1;\n\nfn helper(x: i32) -> i32 {\n    return x;\n}\n\nfn main() -> void {\n    println(\"{}\", A);\n}\n";
+        let edits = [
+            TextEdit::new(15..16, "42"),
+            TextEdit::new(61..61, "\n\nlet b = 2;"),
+            TextEdit::new(66..70, "mian"),
+            TextEdit::new(101..102, ""),
+            TextEdit::new(0..0, "let \"open = 1;\n"),
+        ];
+
+        let file = ParsedFile::parse(source, FileId::default());
+        assert!(file.ast().is_ok());
+
+        for edit in &edits {
+            let new_source = edit.apply(source);
+            let incremental = file.reparse(edit, &new_source);
+            let scratch = ParsedFile::parse(&new_source, FileId::default());
+
+            assert_eq!(incremental.tokens(), scratch.tokens(), "edit {:?}", edit);
+            assert_eq!(incremental.lex_errors(), scratch.lex_errors(), "edit {:?}", edit);
+            assert_eq!(incremental.ast(), scratch.ast(), "edit {:?}", edit);
+
+            let tokens = Lexer::new(&new_source).tokenize_recovering().0;
+            assert_eq!(incremental.ast().ok(), Parser::new(tokens).parse().ok().as_deref());
+        }
+
+        // editing the edited file again keeps working
+        let first = TextEdit::new(15..16, "42");
+        let once = first.apply(source);
+        let second = TextEdit::new(once.len() - 2..once.len() - 2, "    let y = 3;\n");
+        let twice = second.apply(&once);
+        let incremental = file.reparse(&first, &once);
+        assert_eq!(incremental.reparse(&second, &twice).ast(), ParsedFile::parse(&twice, FileId::default()).ast());
+    }
+}
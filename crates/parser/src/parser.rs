@@ -1,5 +1,8 @@
+use std::ops::Range;
+
+use diagnostics::{codes, Diagnostic, Span};
 use lexer::{Token, TokenType};
-use crate::ast::ASTNode;
+use crate::ast::{ASTNode, ArrayTypeExpr, MatchArm, Pattern, TypeExpr};
 
 pub struct Parser<'a> {
     tokens: Vec<Token<'a>>,
@@ -13,58 +16,114 @@ impl<'a> Parser<'a> {
         }
     }
 
-    pub fn parse(&mut self) -> Result<Vec<ASTNode<'a>>, String> {
+    pub fn parse(&mut self) -> Result<Vec<ASTNode<'a>>, Box<Diagnostic>> {
         let mut statements = Vec::new();
+        let mut ranges = Vec::new();
+
+        self.parse_items(&mut statements, &mut ranges, |_| None)?;
+        self.main_function_exists(&statements)?;
 
+        Ok(statements)
+    }
+
+    /// Parses the top-level declarations into `items`, recording the range
+    /// of tokens each one spans. `reuse` is offered the index of the token
+    /// each declaration starts at and may supply an already parsed node,
+    /// together with the index just past its tokens, to take instead.
+    pub(crate) fn parse_items(
+        &mut self,
+        items: &mut Vec<ASTNode<'a>>,
+        ranges: &mut Vec<Range<usize>>,
+        mut reuse: impl FnMut(usize) -> Option<(ASTNode<'a>, usize)>,
+    ) -> Result<(), Box<Diagnostic>> {
         while !self.is_at_end() {
-            statements.push(self.parse_declaration()?);
+            let start = self.current;
+            let item = match reuse(start) {
+                Some((item, end)) => {
+                    self.current = end;
+                    item
+                }
+                None => self.parse_declaration()?,
+            };
+
+            items.push(item);
+            ranges.push(start..self.current);
         }
 
-        self.main_function_exists(&statements)?;
+        Ok(())
+    }
 
-        Ok(statements)
+    pub(crate) fn into_tokens(self) -> Vec<Token<'a>> {
+        self.tokens
     }
 
-    fn parse_declaration(&mut self) -> Result<ASTNode<'a>, String> {
+    fn parse_declaration(&mut self) -> Result<ASTNode<'a>, Box<Diagnostic>> {
         if self.match_token(TokenType::Let) || self.match_token(TokenType::Const) {
             self.parse_variable()
         } else if self.match_token(TokenType::Function) {
             self.parse_function()
         } else if self.match_token(TokenType::Return) {
             self.parse_return()
+        } else if self.match_token(TokenType::If) {
+            self.parse_if()
+        } else if self.match_token(TokenType::While) {
+            self.parse_while()
+        } else if self.match_token(TokenType::For) {
+            self.parse_for()
+        } else if self.match_token(TokenType::ForEach) {
+            self.parse_foreach()
+        } else if self.match_token(TokenType::Break) || self.match_token(TokenType::Continue) {
+            self.parse_loop_control()
         } else {
             self.parse_statement()
         }
     }
 
-    fn parse_type(&mut self) -> Result<Token<'a>, String> {
+    fn parse_type(&mut self) -> Result<TypeExpr<'a>, Box<Diagnostic>> {
         let var_type = &self.tokens[self.current].token_type;
 
         use TokenType::*;
         match var_type {
-            Identifier(_) => Ok(self.advance().clone()),
+            Identifier(_) => Ok(TypeExpr::Named(self.advance().clone())),
 
             ISize | I8 | I16 | I32 | I64 | 
             USize | U8 | U16 | U32 | U64 |
-            F32 | F64 | Char | Bool => Ok(self.advance().clone()),
+            F32 | F64 | Char | Bool => Ok(TypeExpr::Named(self.advance().clone())),
 
-            _ => Err("error: expected a type name".to_string()),
+            LeftBracket => self.parse_array_type(),
+
+            _ => Err(self.error_at_current(codes::EXPECTED_TYPE, "expected a type name").into()),
         }
     }
 
-    fn parse_variable(&mut self) -> Result<ASTNode<'a>, String> {
+    fn parse_array_type(&mut self) -> Result<TypeExpr<'a>, Box<Diagnostic>> {
+        let open = self.advance().clone();
+        let const_elements = self.match_token(TokenType::Const);
+        let element = self.parse_type()?;
+        self.consume(TokenType::Comma, "expected ',' after the element type")?;
+
+        let size = match self.tokens[self.current].token_type {
+            TokenType::IntLiteral { suffix: None, .. } | TokenType::AnySize => self.advance().clone(),
+            _ => return Err(self.error_at_current(codes::EXPECTED_TYPE, "expected an array size or 'anysize'").into()),
+        };
+        let close = self.consume(TokenType::RightBracket, "expected ']' after the array size")?.clone();
+
+        Ok(TypeExpr::Array(Box::new(ArrayTypeExpr { open, const_elements, element, size, close })))
+    }
+
+    fn parse_variable(&mut self) -> Result<ASTNode<'a>, Box<Diagnostic>> {
         let is_const = self.previous().token_type == TokenType::Const;
-        let name = self.consume(TokenType::Identifier("".to_string()), "error: expected variable name")?.clone();
+        let name = self.consume(TokenType::Identifier("".to_string()), "expected variable name")?.clone();
         let mut type_annotation = None;
         
         if self.match_token(TokenType::Colon) {
             type_annotation = Some(self.parse_type()?);
 
         }
-        self.consume(TokenType::Equal, "error: expected '=' after variable name")?;
+        self.consume(TokenType::Equal, "expected '=' after variable name")?;
 
         let initializer = self.parse_expression()?;
-        self.consume(TokenType::Semicolon, "error: expected ';' at the end of line")?;
+        self.consume_semicolon("expected ';' at the end of line")?;
 
         Ok(ASTNode::VariableDeclaration {
             is_const,
@@ -74,80 +133,398 @@ impl<'a> Parser<'a> {
         })
     }
 
-    fn parse_function(&mut self) -> Result<ASTNode<'a>, String> {
-        let name = self.consume(TokenType::Identifier("".to_string()), "error: expected function name")?.clone();
-        self.consume(TokenType::LeftParen, "error: expected '(' after function name")?;
+    fn parse_function(&mut self) -> Result<ASTNode<'a>, Box<Diagnostic>> {
+        let name = self.consume(TokenType::Identifier("".to_string()), "expected function name")?.clone();
+        self.consume(TokenType::LeftParen, "expected '(' after function name")?;
 
         let mut parameters = Vec::new();
         if !self.check(TokenType::RightParen) {
             loop {
-                let param_name = self.consume(TokenType::Identifier("".to_string()), "error: expected parameter name")?.clone();
-                self.consume(TokenType::Colon, "error: expected ':' after parameter name")?;
+                let param_name = self.consume(TokenType::Identifier("".to_string()), "expected parameter name")?.clone();
+                self.consume(TokenType::Colon, "expected ':' after parameter name")?;
 
                 let param_type = self.parse_type()?;
-                parameters.push((param_name.clone(), param_type.clone()));
+                parameters.push((param_name, param_type));
 
                 if !self.match_token(TokenType::Comma) {
                     break;
                 }
             }
         }
-        self.consume(TokenType::RightParen, "error: expected ')' after parameters")?;
-        self.consume(TokenType::Arrow, "error: expected '->' after ')'")?;
+        self.consume(TokenType::RightParen, "expected ')' after parameters")?;
+        self.consume(TokenType::Arrow, "expected '->' after ')'")?;
 
-        let return_type = self.parse_type()?.clone();
-        self.consume(TokenType::LeftBrace, "error: expected '{' to open function body")?;
+        let return_type = self.parse_type()?;
+        self.consume(TokenType::LeftBrace, "expected '{' to open function body")?;
 
         let mut body = Vec::new();
         while !self.check(TokenType::RightBrace) {
             body.push(self.parse_declaration()?);
         }
-        self.consume(TokenType::RightBrace, "error: expected '}' to close function body")?;
+        self.consume(TokenType::RightBrace, "expected '}' to close function body")?;
 
         Ok(ASTNode::FunctionDeclaration {
             name: name.clone(),
             parameters,
-            return_type: return_type.clone(),
+            return_type,
             body,
         })
     }
 
-    fn parse_return(&mut self) -> Result<ASTNode<'a>, String> {
+    fn parse_return(&mut self) -> Result<ASTNode<'a>, Box<Diagnostic>> {
         let value = self.parse_expression()?;
-        self.consume(TokenType::Semicolon, "error: expected ';' after return value")?;
+        self.consume_semicolon("expected ';' after return value")?;
 
         Ok(ASTNode::ReturnStatement {
             value: Box::new(value),
         })
     }
 
-    fn parse_statement(&mut self) -> Result<ASTNode<'a>, String> {
+    fn parse_statement(&mut self) -> Result<ASTNode<'a>, Box<Diagnostic>> {
         let expr = self.parse_expression()?;
-        self.consume(TokenType::Semicolon, "error: expected ';' after expression")?;
+
+        if self.tokens[self.current].token_type.is_assignment() {
+            return self.parse_assignment(expr);
+        }
+
+        // like `if`, a `match` used as a statement needs no semicolon
+        if matches!(expr, ASTNode::Match { .. }) && !self.check(TokenType::Semicolon) {
+            return Ok(expr);
+        }
+
+        self.consume_semicolon("expected ';' after expression")?;
 
         Ok(expr)
     }
 
-    fn parse_expression(&mut self) -> Result<ASTNode<'a>, String> {
+    /// Parses the rest of an `if` after the keyword.
+    fn parse_if(&mut self) -> Result<ASTNode<'a>, Box<Diagnostic>> {
+        let keyword = self.previous().clone();
+        self.consume(TokenType::LeftParen, "expected '(' after 'if'")?;
+        let condition = self.parse_expression()?;
+        self.consume(TokenType::RightParen, "expected ')' after condition")?;
+
+        let then_branch = self.parse_block()?;
+        let else_branch = if self.match_token(TokenType::Else) {
+            if self.match_token(TokenType::If) {
+                Some(Box::new(self.parse_if()?))
+            } else {
+                Some(Box::new(self.parse_block()?))
+            }
+        } else {
+            None
+        };
+
+        Ok(ASTNode::If {
+            keyword,
+            condition: Box::new(condition),
+            then_branch: Box::new(then_branch),
+            else_branch,
+        })
+    }
+
+    fn parse_while(&mut self) -> Result<ASTNode<'a>, Box<Diagnostic>> {
+        let keyword = self.previous().clone();
+        self.consume(TokenType::LeftParen, "expected '(' after 'while'")?;
+        let condition = self.parse_expression()?;
+        self.consume(TokenType::RightParen, "expected ')' after condition")?;
+        let body = self.parse_block()?;
+
+        Ok(ASTNode::While { keyword, condition: Box::new(condition), body: Box::new(body) })
+    }
+
+    fn parse_for(&mut self) -> Result<ASTNode<'a>, Box<Diagnostic>> {
+        let keyword = self.previous().clone();
+        self.consume(TokenType::LeftParen, "expected '(' after 'for'")?;
+        let variable = self.consume(TokenType::Identifier("".to_string()), "expected loop variable name")?.clone();
+        self.consume(TokenType::In, "expected 'in' after loop variable")?;
+
+        let start = self.parse_expression()?;
+        let inclusive = if self.match_token(TokenType::DoubleDotEqual) {
+            true
+        } else {
+            self.consume(TokenType::DoubleDot, "expected '..' or '..=' in range")?;
+            false
+        };
+        let end = self.parse_expression()?;
+        self.consume(TokenType::RightParen, "expected ')' after range")?;
+        let body = self.parse_block()?;
+
+        Ok(ASTNode::For {
+            keyword,
+            variable,
+            start: Box::new(start),
+            end: Box::new(end),
+            inclusive,
+            body: Box::new(body),
+        })
+    }
+
+    fn parse_foreach(&mut self) -> Result<ASTNode<'a>, Box<Diagnostic>> {
+        let keyword = self.previous().clone();
+        self.consume(TokenType::LeftParen, "expected '(' after 'foreach'")?;
+        let variable = self.consume(TokenType::Identifier("".to_string()), "expected loop variable name")?.clone();
+        self.consume(TokenType::In, "expected 'in' after loop variable")?;
+        let collection = self.parse_expression()?;
+        self.consume(TokenType::RightParen, "expected ')' after collection")?;
+        let body = self.parse_block()?;
+
+        Ok(ASTNode::ForEach { keyword, variable, collection: Box::new(collection), body: Box::new(body) })
+    }
+
+    /// Parses the rest of `break` or `continue`, with an optional
+    /// `if (condition)` and block.
+    fn parse_loop_control(&mut self) -> Result<ASTNode<'a>, Box<Diagnostic>> {
+        let keyword = self.previous().clone();
+
+        let mut condition = None;
+        let mut body = None;
+        if self.match_token(TokenType::If) {
+            self.consume(TokenType::LeftParen, "expected '(' after 'if'")?;
+            condition = Some(Box::new(self.parse_expression()?));
+            self.consume(TokenType::RightParen, "expected ')' after condition")?;
+
+            if self.check(TokenType::LeftBrace) {
+                body = Some(Box::new(self.parse_block()?));
+            }
+        }
+        self.consume_semicolon(&format!("expected ';' after '{}'", keyword.lexeme))?;
+
+        Ok(if keyword.token_type == TokenType::Break {
+            ASTNode::Break { keyword, condition, body }
+        } else {
+            ASTNode::Continue { keyword, condition, body }
+        })
+    }
+
+    /// Parses the rest of a `match` after the keyword.
+    fn parse_match(&mut self) -> Result<ASTNode<'a>, Box<Diagnostic>> {
+        let keyword = self.previous().clone();
+        self.consume(TokenType::LeftParen, "expected '(' after 'match'")?;
+        let scrutinee = self.parse_expression()?;
+        self.consume(TokenType::RightParen, "expected ')' after the matched value")?;
+        self.consume(TokenType::LeftBrace, "expected '{' to open match arms")?;
+
+        let mut arms = Vec::new();
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            let pattern = self.parse_pattern()?;
+
+            let mut guard = None;
+            if self.match_token(TokenType::If) {
+                self.consume(TokenType::LeftParen, "expected '(' after 'if'")?;
+                guard = Some(self.parse_expression()?);
+                self.consume(TokenType::RightParen, "expected ')' after condition")?;
+            }
+            self.consume(TokenType::EqualArrow, "expected '=>' after pattern")?;
+
+            // a brace after `=>` starts a block rather than an array literal
+            let body = if self.check(TokenType::LeftBrace) { self.parse_block()? } else { self.parse_expression()? };
+            let is_block = matches!(body, ASTNode::Block { .. });
+            arms.push(MatchArm { pattern, guard, body });
+
+            // the comma is optional after a block
+            if !self.match_token(TokenType::Comma) && !is_block {
+                break;
+            }
+        }
+        let close = self.consume(TokenType::RightBrace, "expected '}' to close match")?.clone();
+
+        Ok(ASTNode::Match { keyword, scrutinee: Box::new(scrutinee), arms, close })
+    }
+
+    fn parse_pattern(&mut self) -> Result<Pattern<'a>, Box<Diagnostic>> {
+        if matches!(self.tokens[self.current].token_type, TokenType::Identifier(_)) {
+            let token = self.advance().clone();
+
+            return Ok(if token.lexeme == "_" { Pattern::Wildcard(token) } else { Pattern::Binding(token) });
+        }
+
+        let start = self.parse_pattern_literal()?;
+        if self.check(TokenType::DoubleDot) {
+            return Err(self.error_at_current(codes::EXPECTED_PATTERN, "exclusive range patterns are not supported")
+                .with_help("use `..=` to include the upper bound").into());
+        }
+        if !self.match_token(TokenType::DoubleDotEqual) {
+            return Ok(Pattern::Literal(Box::new(start)));
+        }
+        let end = self.parse_pattern_literal()?;
+
+        Ok(Pattern::Range { start: Box::new(start), end: Box::new(end) })
+    }
+
+    /// An integer, `char` or `bool` literal, or a negated integer.
+    fn parse_pattern_literal(&mut self) -> Result<ASTNode<'a>, Box<Diagnostic>> {
+        if self.match_token(TokenType::Minus) {
+            let operator = self.previous().clone();
+            if !matches!(self.tokens[self.current].token_type, TokenType::IntLiteral { .. }) {
+                return Err(self.error_at_current(codes::EXPECTED_PATTERN, "expected an integer after '-' in pattern").into());
+            }
+            let operand = ASTNode::Expression { token: self.advance().clone() };
+
+            return Ok(ASTNode::Unary { operator, operand: Box::new(operand) });
+        }
+
+        match self.tokens[self.current].token_type {
+            TokenType::IntLiteral { .. } | TokenType::CharLiteral(_) | TokenType::BoolLiteral(_) => {
+                Ok(ASTNode::Expression { token: self.advance().clone() })
+            }
+            _ => Err(self.error_at_current(codes::EXPECTED_PATTERN, "expected a pattern")
+                .with_help("patterns are literals, ranges such as `1..=5`, names or `_`").into()),
+        }
+    }
+
+    /// Parses `{ ... }`. An expression without a semicolon right before the
+    /// closing brace, or an `if` with an `else` in that position, is the
+    /// block's value.
+    fn parse_block(&mut self) -> Result<ASTNode<'a>, Box<Diagnostic>> {
+        let open = self.consume(TokenType::LeftBrace, "expected '{' to open block")?.clone();
+
+        let mut statements = Vec::new();
+        let mut value = None;
+        while !self.check(TokenType::RightBrace) && !self.is_at_end() {
+            let statement = match self.tokens[self.current].token_type {
+                TokenType::Let
+                | TokenType::Const
+                | TokenType::Function
+                | TokenType::Return
+                | TokenType::If
+                | TokenType::While
+                | TokenType::For
+                | TokenType::ForEach
+                | TokenType::Break
+                | TokenType::Continue => {
+                    self.parse_declaration()?
+                }
+                _ => {
+                    let expr = self.parse_expression()?;
+
+                    if self.tokens[self.current].token_type.is_assignment() {
+                        self.parse_assignment(expr)?
+                    } else if self.check(TokenType::RightBrace) {
+                        value = Some(Box::new(expr));
+                        break;
+                    } else if matches!(expr, ASTNode::Match { .. }) && !self.check(TokenType::Semicolon) {
+                        expr
+                    } else {
+                        self.consume_semicolon("expected ';' after expression")?;
+                        expr
+                    }
+                }
+            };
+
+            if matches!(statement, ASTNode::If { else_branch: Some(_), .. }) && self.check(TokenType::RightBrace) {
+                value = Some(Box::new(statement));
+                break;
+            }
+
+            statements.push(statement);
+        }
+        let close = self.consume(TokenType::RightBrace, "expected '}' to close block")?.clone();
+
+        Ok(ASTNode::Block { open, statements, value, close })
+    }
+
+    fn parse_assignment(&mut self, target: ASTNode<'a>) -> Result<ASTNode<'a>, Box<Diagnostic>> {
+        if !target.is_place() {
+            return Err(Diagnostic::error(codes::INVALID_ASSIGNMENT_TARGET, "invalid left-hand side of assignment")
+                .with_span(target.span())
+                .with_help("only variables, array elements and fields can be assigned to").into());
+        }
+
+        let op = self.advance().clone();
+        let value = self.parse_expression()?;
+        self.consume_semicolon("expected ';' after assignment")?;
+
+        Ok(ASTNode::Assign {
+            target: Box::new(target),
+            op,
+            value: Box::new(value),
+        })
+    }
+
+    fn parse_expression(&mut self) -> Result<ASTNode<'a>, Box<Diagnostic>> {
+        self.parse_binary(0)
+    }
+
+    /// Precedence climbing over the binary operators; see `binary_precedence`
+    /// and the operator table in grammar.md.
+    fn parse_binary(&mut self, min_precedence: u8) -> Result<ASTNode<'a>, Box<Diagnostic>> {
+        let mut left = self.parse_unary()?;
+        let mut after_comparison = false;
+
+        while let Some(precedence) = binary_precedence(&self.tokens[self.current].token_type) {
+            if precedence < min_precedence {
+                break;
+            }
+
+            let is_comparison = precedence == COMPARISON_PRECEDENCE;
+            if is_comparison && after_comparison {
+                return Err(self.error_at_current(codes::CHAINED_COMPARISON, "comparison operators cannot be chained")
+                    .with_help("use parentheses to make the order explicit, such as `(a < b) == c`").into());
+            }
+
+            let operator = self.advance().clone();
+            if operator.token_type == TokenType::As {
+                let target_type = self.parse_type()?;
+                left = ASTNode::Cast { value: Box::new(left), target_type };
+                continue;
+            }
+
+            // every binary operator is left-associative
+            let right = self.parse_binary(precedence + 1)?;
+            left = ASTNode::Binary { left: Box::new(left), operator, right: Box::new(right) };
+            after_comparison = is_comparison;
+        }
+
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self) -> Result<ASTNode<'a>, Box<Diagnostic>> {
+        if self.match_token(TokenType::ExclamationMark) || self.match_token(TokenType::Minus) {
+            let operator = self.previous().clone();
+            let operand = self.parse_unary()?;
+
+            return Ok(ASTNode::Unary { operator, operand: Box::new(operand) });
+        }
+
+        self.parse_call()
+    }
+
+    fn parse_call(&mut self) -> Result<ASTNode<'a>, Box<Diagnostic>> {
         let mut expr = self.parse_primary()?;
         if self.match_token(TokenType::LeftParen) {
             let name_token = match &expr {
                 ASTNode::VariableExpression { name } => name.clone(),
-                _ => return Err("error: expected function name before '('".to_string()),
+                _ => return Err(self.error_at_current(codes::EXPECTED_EXPRESSION, "expected function name before '('").into()),
             };
 
             expr = self.finish_parse_fn_call(name_token)?;
         }
+
+        loop {
+            if self.match_token(TokenType::LeftBracket) {
+                let index = self.parse_expression()?;
+                let close = self.consume(TokenType::RightBracket, "expected ']' after index")?.clone();
+
+                expr = ASTNode::Index { target: Box::new(expr), index: Box::new(index), close };
+            } else if self.match_token(TokenType::Dot) {
+                let field = self.consume(TokenType::Identifier("".to_string()), "expected field name after '.'")?.clone();
+
+                expr = ASTNode::Field { target: Box::new(expr), field };
+            } else {
+                break;
+            }
+        }
         
         Ok(expr)
     }
 
-    fn parse_primary(&mut self) -> Result<ASTNode<'a>, String> {
+    fn parse_primary(&mut self) -> Result<ASTNode<'a>, Box<Diagnostic>> {
         let token_type = &self.tokens[self.current].token_type;
 
         use TokenType::*;
         match token_type {
-            IntLiteral(_) | FloatLiteral(_) | StringLiteral(_) | CharLiteral(_) | BoolLiteral(_) => {
+            IntLiteral { .. } | FloatLiteral { .. } | StringLiteral(_) | CharLiteral(_) | BoolLiteral(_) => {
                 self.advance();
 
                 Ok(ASTNode::Expression { token: self.previous().clone() })
@@ -159,22 +536,52 @@ impl<'a> Parser<'a> {
                 Ok(ASTNode::VariableExpression { name: self.previous().clone() })
             }
 
+            If => {
+                self.advance();
+
+                self.parse_if()
+            }
+
+            Match => {
+                self.advance();
+
+                self.parse_match()
+            }
+
+            LeftBrace => self.parse_array_literal(),
+
             LeftParen => {
                 self.advance();
 
                 let expr = self.parse_expression()?;
-                self.consume(TokenType::RightParen, "error: expected ')' after expression");
+                self.consume(TokenType::RightParen, "expected ')' after expression")?;
 
                 Ok(expr)
             }
 
             _ => {
-                Err("error: expected primary expression".to_string())
+                Err(self.error_at_current(codes::EXPECTED_EXPRESSION, "expected primary expression").into())
             }
         }
     }
 
-    fn finish_parse_fn_call(&mut self, name: Token<'a>) -> Result<ASTNode<'a>, String> {
+    fn parse_array_literal(&mut self) -> Result<ASTNode<'a>, Box<Diagnostic>> {
+        let open = self.advance().clone();
+
+        let mut elements = Vec::new();
+        while !self.check(TokenType::RightBrace) {
+            elements.push(self.parse_expression()?);
+
+            if !self.match_token(TokenType::Comma) {
+                break;
+            }
+        }
+        let close = self.consume(TokenType::RightBrace, "expected '}' to close array literal")?.clone();
+
+        Ok(ASTNode::ArrayLiteral { open, elements, close })
+    }
+
+    fn finish_parse_fn_call(&mut self, name: Token<'a>) -> Result<ASTNode<'a>, Box<Diagnostic>> {
         let mut args = Vec::new();
 
         if !self.check(TokenType::RightParen) {
@@ -186,15 +593,15 @@ impl<'a> Parser<'a> {
                 }
             }
         }
-        self.consume(TokenType::RightParen, "error: expected ')' to close function body");
+        self.consume(TokenType::RightParen, "expected ')' to close function body")?;
 
         Ok(ASTNode::FunctionCallExpression { name, arguments: args })
     }
 
-    fn main_function_exists(&self, ast: &[ASTNode<'a>]) -> Result<(), String> {
+    pub(crate) fn main_function_exists(&self, ast: &[ASTNode<'a>]) -> Result<(), Box<Diagnostic>> {
         let main_found = ast.iter().any(|node| {
             if let ASTNode::FunctionDeclaration { name, parameters, return_type, .. } = node {
-                name.lexeme == "main" && parameters.is_empty() && return_type.lexeme == "void"
+                name.lexeme == "main" && parameters.is_empty() && return_type.is_void()
             } else {
                 false
             }
@@ -203,9 +610,8 @@ impl<'a> Parser<'a> {
         if main_found {
             Ok(())
         } else {
-            Err("error: no 'main' function found\n
-                help: your program must have an entry declared as\n\tfn main() -> void"
-            .to_string())
+            Err(Diagnostic::error(codes::MISSING_MAIN, "no 'main' function found")
+                .with_help("your program must have an entry declared as `fn main() -> void`").into())
         }
     }
 
@@ -219,14 +625,30 @@ impl<'a> Parser<'a> {
         }
     }
 
-    fn consume(&mut self, token: TokenType, msg: &str) -> Result<&Token<'a>, String> {
+    fn consume(&mut self, token: TokenType, msg: &str) -> Result<&Token<'a>, Box<Diagnostic>> {
         if self.check(token) {
             Ok(self.advance())
         } else {
-            Err(msg.to_string())
+            Err(self.error_at_current(codes::EXPECTED_TOKEN, msg).into())
         }
     }
 
+    fn consume_semicolon(&mut self, msg: &str) -> Result<&Token<'a>, Box<Diagnostic>> {
+        if self.check(TokenType::Semicolon) {
+            return Ok(self.advance());
+        }
+
+        let previous = self.previous().span;
+        let insert_at = Span::new(previous.file_id, previous.hi, previous.hi);
+
+        Err(self.error_at_current(codes::EXPECTED_TOKEN, msg)
+            .with_suggestion(insert_at, ";", "add a semicolon").into())
+    }
+
+    fn error_at_current(&self, code: &'static str, msg: &str) -> Diagnostic {
+        Diagnostic::error(code, msg).with_span(self.tokens[self.current].span)
+    }
+
     fn check(&self, token: TokenType) -> bool {
         if self.is_at_end() {
             return false;
@@ -235,8 +657,6 @@ impl<'a> Parser<'a> {
         use TokenType::*;
         match (&self.tokens[self.current].token_type, &token) {
             (Identifier(_), Identifier(_)) => true,
-            (IntLiteral(_), IntLiteral(_)) => true,
-            (FloatLiteral(_), FloatLiteral(_)) => true,
             (StringLiteral(_), StringLiteral(_)) => true,
             (a, b) => std::mem::discriminant(a) == std::mem::discriminant(b),
         }
@@ -258,3 +678,30 @@ impl<'a> Parser<'a> {
         &self.tokens[self.current - 1]
     }
 }
+
+const COMPARISON_PRECEDENCE: u8 = 3;
+
+/// The binding power of each binary operator, from `||` (loosest) to `as`
+/// (tightest). Prefix `!` and `-` bind tighter than all of them.
+fn binary_precedence(token_type: &TokenType) -> Option<u8> {
+    let precedence = match token_type {
+        TokenType::DoublePipe => 1,
+        TokenType::DoubleAmpersand => 2,
+        TokenType::DoubleEqual
+        | TokenType::ExclamEqual
+        | TokenType::LeftAngle
+        | TokenType::RightAngle
+        | TokenType::LessEqual
+        | TokenType::GreaterEqual => COMPARISON_PRECEDENCE,
+        TokenType::Pipe => 4,
+        TokenType::Carrot => 5,
+        TokenType::Ampersand => 6,
+        TokenType::DoubleLeftAngle | TokenType::DoubleRightAngle => 7,
+        TokenType::Plus | TokenType::Minus => 8,
+        TokenType::Star | TokenType::ForwardSlash | TokenType::Modulo => 9,
+        TokenType::As => 10,
+        _ => return None,
+    };
+
+    Some(precedence)
+}
@@ -0,0 +1,289 @@
+use std::collections::HashMap;
+
+use diagnostics::{codes, Diagnostic, Span};
+use lexer::Token;
+
+use crate::types::Type;
+use crate::{ASTNode, MatchArm, Pattern};
+
+/// Checks rules that depend on where a construct appears rather than on
+/// types, such as `break` only being valid inside a loop, that every
+/// `match` covers all values, and that functions returning a value do so on
+/// every path. Runs after the type checker.
+pub struct SemanticChecker<'t> {
+    loop_depth: usize,
+    /// See `TypeChecker::match_types`.
+    match_types: &'t HashMap<Span, Type>,
+    warnings: Vec<Diagnostic>,
+}
+
+impl<'t> SemanticChecker<'t> {
+    pub fn new(match_types: &'t HashMap<Span, Type>) -> Self {
+        Self { loop_depth: 0, match_types, warnings: Vec::new() }
+    }
+
+    /// Problems found that do not stop compilation, such as unreachable
+    /// `match` arms.
+    pub fn warnings(&self) -> &[Diagnostic] {
+        &self.warnings
+    }
+
+    pub fn check(&mut self, ast: &[ASTNode<'_>]) -> Result<(), Box<Diagnostic>> {
+        for node in ast {
+            self.check_node(node)?;
+        }
+
+        Ok(())
+    }
+
+    fn check_node(&mut self, node: &ASTNode<'_>) -> Result<(), Box<Diagnostic>> {
+        match node {
+            ASTNode::FunctionDeclaration { name, return_type, body, .. } => {
+                // a nested function does not see the loops around it
+                let loop_depth = std::mem::take(&mut self.loop_depth);
+                self.check(body)?;
+                self.loop_depth = loop_depth;
+
+                if !return_type.is_void() && !body.iter().any(always_returns) {
+                    return Err(Diagnostic::error(codes::MISSING_RETURN, "not all paths return a value")
+                        .with_span(name.span)
+                        .with_label(return_type.span(), format!("`{}` is declared to return a value", name.lexeme))
+                        .with_help("add a `return` for the paths that reach the end of the function").into());
+                }
+            }
+
+            ASTNode::While { condition, body, .. } => {
+                self.check_node(condition)?;
+
+                self.loop_depth += 1;
+                self.check_node(body)?;
+                self.loop_depth -= 1;
+            }
+
+            ASTNode::For { start, end, body, .. } => {
+                self.check_node(start)?;
+                self.check_node(end)?;
+
+                self.loop_depth += 1;
+                self.check_node(body)?;
+                self.loop_depth -= 1;
+            }
+
+            ASTNode::ForEach { collection, body, .. } => {
+                self.check_node(collection)?;
+
+                self.loop_depth += 1;
+                self.check_node(body)?;
+                self.loop_depth -= 1;
+            }
+
+            ASTNode::Break { keyword, condition, body } | ASTNode::Continue { keyword, condition, body } => {
+                if self.loop_depth == 0 {
+                    return Err(Diagnostic::error(codes::OUTSIDE_LOOP, format!("`{}` outside of a loop", keyword.lexeme))
+                        .with_span(keyword.span).into());
+                }
+
+                if let Some(condition) = condition {
+                    self.check_node(condition)?;
+                }
+                if let Some(body) = body {
+                    self.check_node(body)?;
+                }
+            }
+
+            ASTNode::Block { statements, value, .. } => {
+                self.check(statements)?;
+                if let Some(value) = value {
+                    self.check_node(value)?;
+                }
+            }
+
+            ASTNode::If { condition, then_branch, else_branch, .. } => {
+                self.check_node(condition)?;
+                self.check_node(then_branch)?;
+                if let Some(else_branch) = else_branch {
+                    self.check_node(else_branch)?;
+                }
+            }
+
+            ASTNode::Match { keyword, scrutinee, arms, .. } => {
+                self.check_node(scrutinee)?;
+                for arm in arms {
+                    if let Some(guard) = &arm.guard {
+                        self.check_node(guard)?;
+                    }
+                    self.check_node(&arm.body)?;
+                }
+
+                self.check_match_arms(keyword, scrutinee, arms)?;
+            }
+
+            ASTNode::VariableDeclaration { initializer, .. } => self.check_node(initializer)?,
+            ASTNode::ReturnStatement { value } => self.check_node(value)?,
+            ASTNode::Assign { target, value, .. } => {
+                self.check_node(target)?;
+                self.check_node(value)?;
+            }
+            ASTNode::FunctionCallExpression { arguments, .. } => self.check(arguments)?,
+            ASTNode::Binary { left, right, .. } => {
+                self.check_node(left)?;
+                self.check_node(right)?;
+            }
+            ASTNode::Unary { operand, .. } => self.check_node(operand)?,
+            ASTNode::Cast { value, .. } => self.check_node(value)?,
+            ASTNode::ArrayLiteral { elements, .. } => self.check(elements)?,
+            ASTNode::Index { target, index, .. } => {
+                self.check_node(target)?;
+                self.check_node(index)?;
+            }
+            ASTNode::Field { target, .. } => self.check_node(target)?,
+
+            ASTNode::VariableExpression { .. } | ASTNode::Primtive { .. } | ASTNode::Expression { .. } => {}
+        }
+
+        Ok(())
+    }
+
+    /// Walks the arms in order, keeping the ranges of values that no arm has
+    /// taken yet. An arm that matches none of them is unreachable, and any
+    /// left at the end are not covered. Arms with a guard might not take the
+    /// values they match, so they cover nothing.
+    fn check_match_arms(&mut self, keyword: &Token<'_>, scrutinee: &ASTNode<'_>, arms: &[MatchArm<'_>]) -> Result<(), Box<Diagnostic>> {
+        let ty = &self.match_types[&keyword.span];
+
+        let mut uncovered = value_ranges(ty);
+        for arm in arms {
+            let (lo, hi) = match &arm.pattern {
+                Pattern::Wildcard(_) | Pattern::Binding(_) => (i128::MIN, i128::MAX),
+                Pattern::Literal(value) => {
+                    let value = value.literal_value().expect("type checker only allows literal patterns");
+                    (value, value)
+                }
+                Pattern::Range { start, end } => (
+                    start.literal_value().expect("type checker only allows literal patterns"),
+                    end.literal_value().expect("type checker only allows literal patterns"),
+                ),
+            };
+
+            if !uncovered.iter().any(|&(start, end)| start <= hi && lo <= end) {
+                self.warnings.push(Diagnostic::warning(codes::UNREACHABLE_PATTERN, "unreachable pattern")
+                    .with_span(arm.pattern.span())
+                    .with_note("every value it matches is taken by an earlier arm"));
+            }
+
+            if arm.guard.is_none() {
+                uncovered = remove_range(&uncovered, lo, hi);
+            }
+        }
+
+        if uncovered.is_empty() {
+            return Ok(());
+        }
+
+        let missing: Vec<String> = if *ty == Type::Bool {
+            uncovered.iter().flat_map(|&(lo, hi)| lo..=hi).map(|value| (value != 0).to_string()).collect()
+        } else if ty.is_integer() || *ty == Type::Char {
+            uncovered.iter().map(|&(lo, hi)| describe_range(ty, lo, hi)).collect()
+        } else {
+            vec!["_".to_string()]
+        };
+
+        Err(Diagnostic::error(codes::NON_EXHAUSTIVE_MATCH, format!("non-exhaustive patterns: {} not covered", list(&missing)))
+            .with_span(scrutinee.span())
+            .with_label(keyword.span, format!("this `match` over `{}` needs an arm for every value", ty))
+            .with_help("add arms for the missing values, or a `_` arm for everything else").into())
+    }
+}
+
+/// Whether every path through `node` reaches a `return`, so that whatever
+/// follows it never runs. Loops other than `while (true)` might not run their
+/// body, and a `match` that misses values is reported separately.
+fn always_returns(node: &ASTNode<'_>) -> bool {
+    match node {
+        ASTNode::ReturnStatement { .. } => true,
+        ASTNode::Block { statements, value, .. } => {
+            statements.iter().any(always_returns) || value.as_deref().is_some_and(always_returns)
+        }
+        ASTNode::If { then_branch, else_branch: Some(else_branch), .. } => {
+            always_returns(then_branch) && always_returns(else_branch)
+        }
+        ASTNode::Match { arms, .. } => !arms.is_empty() && arms.iter().all(|arm| always_returns(&arm.body)),
+        ASTNode::While { condition, body, .. } => condition.literal_value() == Some(1) && !breaks_out(body),
+        _ => false,
+    }
+}
+
+/// Whether `node` has a `break` that leaves the loop it is the body of,
+/// rather than one inside a nested loop.
+fn breaks_out(node: &ASTNode<'_>) -> bool {
+    match node {
+        ASTNode::Break { .. } => true,
+        ASTNode::Block { statements, value, .. } => {
+            statements.iter().any(breaks_out) || value.as_deref().is_some_and(breaks_out)
+        }
+        ASTNode::If { then_branch, else_branch, .. } => {
+            breaks_out(then_branch) || else_branch.as_deref().is_some_and(breaks_out)
+        }
+        ASTNode::Match { arms, .. } => arms.iter().any(|arm| breaks_out(&arm.body)),
+        _ => false,
+    }
+}
+
+/// Every value of `ty` as sorted, disjoint inclusive ranges. A type that
+/// literal patterns cannot match is one range that only a catch-all covers.
+fn value_ranges(ty: &Type) -> Vec<(i128, i128)> {
+    match ty {
+        Type::Bool => vec![(0, 1)],
+        // a `char` is a unicode scalar value, which leaves out the surrogates
+        Type::Char => vec![(0, 0xD7FF), (0xE000, char::MAX as i128)],
+        _ => vec![ty.int_range().unwrap_or((i128::MIN, i128::MAX))],
+    }
+}
+
+fn remove_range(ranges: &[(i128, i128)], lo: i128, hi: i128) -> Vec<(i128, i128)> {
+    let mut remaining = Vec::new();
+    for &(start, end) in ranges {
+        if end < lo || start > hi {
+            remaining.push((start, end));
+            continue;
+        }
+
+        if start < lo {
+            remaining.push((start, lo - 1));
+        }
+        if end > hi {
+            remaining.push((hi + 1, end));
+        }
+    }
+
+    remaining
+}
+
+/// Writes `lo..=hi` the way a pattern would, using `i32::MIN` and the like
+/// for the ends of an integer type.
+fn describe_range(ty: &Type, lo: i128, hi: i128) -> String {
+    let describe = |value: i128| match (ty, ty.int_range()) {
+        (Type::Char, _) => format!("{:?}", char::from_u32(value as u32).expect("ranges only hold scalar values")),
+        (_, Some((min, _))) if value == min && min != 0 => format!("{}::MIN", ty),
+        (_, Some((_, max))) if value == max => format!("{}::MAX", ty),
+        _ => value.to_string(),
+    };
+
+    if lo == hi {
+        describe(lo)
+    } else {
+        format!("{}..={}", describe(lo), describe(hi))
+    }
+}
+
+/// "`a`", "`a` and `b`", "`a`, `b` and `c`", or the first three and how many
+/// more there are.
+fn list(items: &[String]) -> String {
+    let shown: Vec<String> = items.iter().take(3).map(|item| format!("`{}`", item)).collect();
+
+    match items.len() {
+        1 => shown[0].clone(),
+        2 | 3 => format!("{} and {}", shown[..shown.len() - 1].join(", "), shown[shown.len() - 1]),
+        n => format!("{} and {} more", shown.join(", "), n - 3),
+    }
+}
@@ -1,17 +1,97 @@
-use crate::ASTNode;
+use std::collections::HashMap;
+
+use diagnostics::{codes, Diagnostic, Span};
+use lexer::{Token, TokenType};
+
+use crate::types::Type;
+use crate::{ASTNode, MatchArm, Pattern, TypeExpr};
+
+struct FunctionSignature {
+    parameters: Vec<Type>,
+    return_type: Type,
+}
+
+/// A variable in scope.
+struct Binding {
+    ty: Type,
+    is_const: bool,
+    /// The name in the declaration, for pointing back at it.
+    span: Span,
+}
 
 pub struct TypeChecker<'a> {
-    _phantom: std::marker::PhantomData<&'a ()>,
+    scopes: Vec<HashMap<&'a str, Binding>>,
+    functions: HashMap<&'a str, FunctionSignature>,
+    return_type: Option<Type>,
+    /// The type of each `match`'s scrutinee, by the span of its keyword.
+    match_types: HashMap<Span, Type>,
+    /// The type each unsuffixed literal expression took on, by its span.
+    literal_types: HashMap<Span, Type>,
+    /// Errors that leave every type intact, such as assigning to something
+    /// `const`, so checking carries on past them to report each one.
+    errors: Vec<Diagnostic>,
+}
+
+impl<'a> Default for TypeChecker<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<'a> TypeChecker<'a> {
     pub fn new() -> Self {
         TypeChecker {
-            _phantom: std::marker::PhantomData,
+            scopes: vec![HashMap::new()],
+            functions: HashMap::new(),
+            return_type: None,
+            match_types: HashMap::new(),
+            literal_types: HashMap::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// The type of each `match`'s scrutinee, by the span of its keyword, for
+    /// the semantic checker's exhaustiveness checks.
+    pub fn match_types(&self) -> &HashMap<Span, Type> {
+        &self.match_types
+    }
+
+    /// The type each unsuffixed literal expression took on from its context,
+    /// by its span, so the code generator can give it that type without
+    /// generating the context first.
+    pub fn literal_types(&self) -> &HashMap<Span, Type> {
+        &self.literal_types
+    }
+
+    /// Checks every declaration, returning all errors found. Checking stops
+    /// at the first error that leaves a type unknown, which comes last.
+    pub fn check(&mut self, ast: &[ASTNode<'a>]) -> Result<(), Vec<Diagnostic>> {
+        let result = self.check_items(ast);
+
+        let mut errors = std::mem::take(&mut self.errors);
+        if let Err(error) = result {
+            errors.push(*error);
         }
+
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
     }
 
-    pub fn check(&mut self, ast: &Vec<ASTNode<'a>>) -> Result<(), String> {
+    fn check_items(&mut self, ast: &[ASTNode<'a>]) -> Result<(), Box<Diagnostic>> {
+        // Collect every signature first so functions can call each other in any order.
+        for node in ast {
+            if let ASTNode::FunctionDeclaration { name, parameters, return_type, .. } = node {
+                let signature = FunctionSignature {
+                    parameters: parameters
+                        .iter()
+                        .map(|(_, param_type)| self.resolve_parameter_type(param_type))
+                        .collect::<Result<_, _>>()?,
+                    return_type: self.resolve_type(return_type)?,
+                };
+
+                self.functions.insert(name.lexeme, signature);
+            }
+        }
+
         for node in ast {
             self.check_node(node)?;
         }
@@ -19,65 +99,782 @@ impl<'a> TypeChecker<'a> {
         Ok(())
     }
 
-    fn check_node(&mut self, node: &ASTNode<'a>) -> Result<(), String> {
+    fn check_node(&mut self, node: &ASTNode<'a>) -> Result<(), Box<Diagnostic>> {
         match node {
             ASTNode::FunctionDeclaration { name: _, parameters, return_type, body } => {
-                self.validate_type(&return_type.lexeme)?;
-
-                for (_param_name, param_type) in parameters {
-                    self.validate_type(&param_type.lexeme)?;
+                self.scopes.push(HashMap::new());
+                for (param_name, param_type) in parameters {
+                    let ty = self.resolve_parameter_type(param_type)?;
+                    self.declare(param_name, ty, false);
                 }
+                self.return_type = Some(self.resolve_type(return_type)?);
 
                 for stmt in body {
                     self.check_node(stmt)?;
                 }
+
+                self.return_type = None;
+                self.scopes.pop();
             }
 
-            ASTNode::VariableDeclaration { is_const: _, name: _, type_annotation, initializer } => {
-                if let Some(type_tok) = type_annotation {
-                    self.validate_type(&type_tok.lexeme)?;
-                }
+            ASTNode::VariableDeclaration { is_const, name, type_annotation, initializer } => {
+                let annotated = match type_annotation {
+                    Some(type_tok) => Some(self.resolve_type(type_tok)?),
+                    None => None,
+                };
 
-                self.check_node(initializer)?
+                // the annotation wins, such as for a copy that drops `const` from its elements
+                let ty = self.check_expr(initializer, annotated.as_ref())?;
+                // a variable would share the elements with the parameter
+                // instead of holding a copy, which `anysize` does not allow
+                if let Type::Array { len: None, .. } = ty {
+                    self.errors.push(Diagnostic::error(codes::ANYSIZE_NOT_ALLOWED, format!("cannot store `{}` in a variable", ty))
+                        .with_span(initializer.span())
+                        .with_note("`anysize` is only allowed in parameter types")
+                        .with_help("use the parameter itself"));
+                }
+                self.declare(name, annotated.unwrap_or(ty), *is_const);
             }
 
             ASTNode::ReturnStatement { value } => {
-                self.check_node(value)?;
+                let expected = self.return_type.clone();
+                self.check_expr(value, expected.as_ref())?;
+            }
+
+            ASTNode::Assign { target, op, value } => {
+                self.check_assign(target, op, value)?;
+            }
+
+            ASTNode::If { condition, then_branch, else_branch, .. } => {
+                // as a statement the branches' values are discarded
+                self.check_expr(condition, Some(&Type::Bool))?;
+                self.check_block(then_branch, None)?;
+                if let Some(else_branch) = else_branch {
+                    self.check_node(else_branch)?;
+                }
+            }
+
+            ASTNode::Block { .. } => {
+                self.check_block(node, None)?;
+            }
+
+            ASTNode::While { condition, body, .. } => {
+                self.check_expr(condition, Some(&Type::Bool))?;
+                self.check_block(body, None)?;
+            }
+
+            ASTNode::For { variable, start, end, body, .. } => {
+                let ty = self.check_operands(start, end, None)?;
+                if !ty.is_integer() {
+                    return Err(Diagnostic::error(codes::INVALID_RANGE, format!("range bounds must be integers, found `{}`", ty))
+                        .with_span(start.span().to(end.span())).into());
+                }
+
+                // the loop counts with the variable, so the body cannot change it
+                self.scopes.push(HashMap::new());
+                self.declare(variable, ty, true);
+                self.check_block(body, None)?;
+                self.scopes.pop();
+            }
+
+            ASTNode::ForEach { variable, collection, body, .. } => {
+                let collection_ty = self.check_expr(collection, None)?;
+                let Some(element) = collection_ty.element_type() else {
+                    return Err(Diagnostic::error(codes::NOT_ITERABLE, format!("`{}` is not iterable", collection_ty))
+                        .with_span(collection.span())
+                        .with_help("`foreach` works over arrays").into());
+                };
+
+                self.scopes.push(HashMap::new());
+                self.declare(variable, element, true);
+                self.check_block(body, None)?;
+                self.scopes.pop();
+            }
+
+            ASTNode::Match { keyword, scrutinee, arms, .. } => {
+                // as a statement the arms' values are discarded
+                self.check_match(keyword, scrutinee, arms, None, false)?;
+            }
+
+            ASTNode::Break { condition, body, .. } | ASTNode::Continue { condition, body, .. } => {
+                if let Some(condition) = condition {
+                    self.check_expr(condition, Some(&Type::Bool))?;
+                }
+                if let Some(body) = body {
+                    self.check_block(body, None)?;
+                }
+            }
+
+            _ => {
+                self.check_expr(node, None)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Infers the type of `node`. When `expected` is given the expression must
+    /// have that type, and unsuffixed literals take it on.
+    fn check_expr(&mut self, node: &ASTNode<'a>, expected: Option<&Type>) -> Result<Type, Box<Diagnostic>> {
+        let ty = match node {
+            ASTNode::Expression { token } | ASTNode::Primtive { token } => {
+                self.check_literal(token, expected)?
+            }
+
+            ASTNode::VariableExpression { name } => self.lookup(name)?,
+
+            ASTNode::FunctionCallExpression { name, arguments } => {
+                self.check_call(name, arguments)?
+            }
+
+            ASTNode::Binary { left, operator, right } => {
+                self.check_binary(left, operator, right, expected)?
+            }
+
+            ASTNode::Unary { operator, operand } => {
+                self.check_unary(operator, operand, expected)?
+            }
+
+            ASTNode::Cast { value, target_type } => {
+                let target = self.resolve_type(target_type)?;
+                let source = self.check_expr(value, None)?;
+
+                if !is_valid_cast(&source, &target) {
+                    return Err(Diagnostic::error(codes::INVALID_CAST, format!("casting `{}` as `{}` is invalid", source, target))
+                        .with_span(node.span()).into());
+                }
+
+                target
+            }
+
+            ASTNode::Block { .. } => self.check_block(node, expected)?,
+
+            ASTNode::If { keyword, condition, then_branch, else_branch } => {
+                self.check_expr(condition, Some(&Type::Bool))?;
+
+                let Some(else_branch) = else_branch else {
+                    return Err(Diagnostic::error(codes::IF_WITHOUT_ELSE, "`if` expression is missing an `else` branch")
+                        .with_span(keyword.span)
+                        .with_note("an `if` used as a value needs a value for both outcomes").into());
+                };
+
+                // both branches must agree; an unsuffixed literal takes the other branch's type
+                if then_branch.is_untyped_literal() && !else_branch.is_untyped_literal() {
+                    let ty = self.check_expr(else_branch, expected)?;
+                    self.check_expr(then_branch, Some(&ty))?
+                } else {
+                    let ty = self.check_expr(then_branch, expected)?;
+                    self.check_expr(else_branch, Some(&ty))?
+                }
+            }
+
+            ASTNode::Match { keyword, scrutinee, arms, .. } => self.check_match(keyword, scrutinee, arms, expected, true)?,
+
+            ASTNode::ArrayLiteral { .. } => self.check_array_literal(node, expected)?,
+
+            ASTNode::Index { target, index, .. } => {
+                let ty = self.check_expr(target, None)?;
+                let Type::Array { element, .. } = ty else {
+                    return Err(Diagnostic::error(codes::NOT_INDEXABLE, format!("cannot index into a value of type `{}`", ty))
+                        .with_span(node.span()).into());
+                };
+
+                // any integer type can index; a negative index is out of bounds
+                let index_ty = self.check_expr(index, None)?;
+                if !index_ty.is_integer() {
+                    return Err(Diagnostic::error(codes::INVALID_INDEX, format!("array index must be an integer, found `{}`", index_ty))
+                        .with_span(index.span()).into());
+                }
+
+                *element
+            }
+
+            ASTNode::Field { target, field } => {
+                let ty = self.check_expr(target, None)?;
+
+                return Err(Diagnostic::error(codes::UNKNOWN_FIELD, format!("no field `{}` on type `{}`", field.lexeme, ty))
+                    .with_span(field.span).into());
+            }
+
+            ASTNode::FunctionDeclaration { .. }
+            | ASTNode::VariableDeclaration { .. }
+            | ASTNode::ReturnStatement { .. }
+            | ASTNode::Assign { .. }
+            | ASTNode::While { .. }
+            | ASTNode::For { .. }
+            | ASTNode::ForEach { .. }
+            | ASTNode::Break { .. }
+            | ASTNode::Continue { .. } => {
+                return Err(Diagnostic::error(codes::EXPECTED_EXPRESSION, "expected an expression")
+                    .with_span(node.span()).into());
+            }
+        };
+
+        if let Some(expected) = expected {
+            self.expect_type(expected, &ty, node.span())?;
+        }
+        if node.is_untyped_literal() {
+            self.literal_types.insert(node.span(), ty.clone());
+        }
+
+        Ok(ty)
+    }
+
+    /// Checks every arm against the type of `scrutinee`. As an expression all
+    /// arms must have the same type, which is returned.
+    fn check_match(
+        &mut self,
+        keyword: &Token<'a>,
+        scrutinee: &ASTNode<'a>,
+        arms: &[MatchArm<'a>],
+        expected: Option<&Type>,
+        is_expression: bool,
+    ) -> Result<Type, Box<Diagnostic>> {
+        let scrutinee_ty = self.check_expr(scrutinee, None)?;
+        self.match_types.insert(keyword.span, scrutinee_ty.clone());
+
+        // unsuffixed literals take the type of the first arm that has one,
+        // which is checked first and so only once
+        let inferred = match expected {
+            None if is_expression && !arms.is_empty() => {
+                Some(arms.iter().position(|arm| !arm.body.is_untyped_literal()).unwrap_or(0))
+            }
+            _ => None,
+        };
+        let ty = match inferred {
+            Some(i) => Some(self.check_arm(&arms[i], &scrutinee_ty, None)?),
+            None => expected.cloned(),
+        };
+
+        for (i, arm) in arms.iter().enumerate() {
+            if inferred != Some(i) {
+                self.check_arm(arm, &scrutinee_ty, ty.as_ref())?;
+            }
+        }
+
+        Ok(ty.unwrap_or(Type::Void))
+    }
+
+    fn check_arm(&mut self, arm: &MatchArm<'a>, scrutinee_ty: &Type, expected: Option<&Type>) -> Result<Type, Box<Diagnostic>> {
+        self.scopes.push(HashMap::new());
+        match &arm.pattern {
+            Pattern::Wildcard(_) => {}
+            Pattern::Binding(name) => self.declare(name, scrutinee_ty.clone(), true),
+            Pattern::Literal(value) => {
+                self.check_expr(value, Some(scrutinee_ty))?;
+            }
+            Pattern::Range { start, end } => {
+                self.check_expr(start, Some(scrutinee_ty))?;
+                self.check_expr(end, Some(scrutinee_ty))?;
+
+                if !scrutinee_ty.is_integer() && *scrutinee_ty != Type::Char {
+                    return Err(Diagnostic::error(
+                        codes::INVALID_RANGE,
+                        format!("range patterns need integer or `char` bounds, found `{}`", scrutinee_ty),
+                    )
+                    .with_span(arm.pattern.span()).into());
+                }
+                if start.literal_value() > end.literal_value() {
+                    return Err(Diagnostic::error(codes::INVALID_RANGE, "lower bound of range pattern is greater than its upper bound")
+                        .with_span(arm.pattern.span()).into());
+                }
+            }
+        }
+
+        if let Some(guard) = &arm.guard {
+            self.check_expr(guard, Some(&Type::Bool))?;
+        }
+        let ty = self.check_expr(&arm.body, expected)?;
+        self.scopes.pop();
+
+        Ok(ty)
+    }
+
+    fn check_array_literal(&mut self, literal: &ASTNode<'a>, expected: Option<&Type>) -> Result<Type, Box<Diagnostic>> {
+        let ASTNode::ArrayLiteral { elements, .. } = literal else {
+            unreachable!("only called for array literals");
+        };
+
+        let (element, const_elements) = match expected {
+            Some(Type::Array { element, const_elements, .. }) => ((**element).clone(), *const_elements),
+            _ => {
+                // unsuffixed literals take the type of the first element that has one
+                let Some(first) = elements.iter().find(|e| !e.is_untyped_literal()).or(elements.first()) else {
+                    return Err(Diagnostic::error(codes::TYPE_ANNOTATIONS_NEEDED, "cannot infer the element type of an empty array")
+                        .with_span(literal.span())
+                        .with_help("give the variable a type such as `[i32, 0]`").into());
+                };
+
+                (self.check_expr(first, None)?, false)
+            }
+        };
+
+        for element_node in elements {
+            self.check_expr(element_node, Some(&element))?;
+        }
+
+        Ok(Type::Array { element: Box::new(element), len: Some(elements.len() as u64), const_elements })
+    }
+
+    fn check_literal(&self, token: &Token<'a>, expected: Option<&Type>) -> Result<Type, Box<Diagnostic>> {
+        let ty = match &token.token_type {
+            TokenType::IntLiteral { value, suffix } => {
+                let ty = match (suffix, expected) {
+                    (Some(suffix), _) => Type::from(*suffix),
+                    (None, Some(expected)) if expected.is_integer() => expected.clone(),
+                    (None, _) => Type::I32,
+                };
+
+                if let Some((min, max)) = ty.int_range() {
+                    if *value > max as u128 {
+                        return Err(out_of_range(token, &ty)
+                            .with_note(format!("the type `{}` can only hold values in `{}..={}`", ty, min, max)).into());
+                    }
+                }
+
+                ty
             }
 
-            ASTNode::FunctionCallExpression { name: _, arguments } => {
-                for arg in arguments {
-                    self.check_node(arg)?;
+            TokenType::FloatLiteral { value, suffix } => {
+                let ty = match (suffix, expected) {
+                    (Some(suffix), _) => Type::from(*suffix),
+                    (None, Some(expected)) if expected.is_float() => expected.clone(),
+                    (None, _) => Type::F64,
+                };
+
+                if ty == Type::F32 && (*value as f32).is_infinite() {
+                    return Err(out_of_range(token, &ty)
+                        .with_note(format!("the type `f32` can only hold values up to `{:e}`", f32::MAX)).into());
                 }
+
+                ty
+            }
+
+            TokenType::StringLiteral(_) => Type::Str,
+            TokenType::CharLiteral(_) => Type::Char,
+            TokenType::BoolLiteral(_) => Type::Bool,
+
+            _ => {
+                return Err(Diagnostic::error(codes::EXPECTED_EXPRESSION, "expected a literal")
+                    .with_span(token.span).into());
+            }
+        };
+
+        Ok(ty)
+    }
+
+    fn check_binary(
+        &mut self,
+        left: &ASTNode<'a>,
+        operator: &Token<'a>,
+        right: &ASTNode<'a>,
+        expected: Option<&Type>,
+    ) -> Result<Type, Box<Diagnostic>> {
+        match &operator.token_type {
+            TokenType::DoubleAmpersand | TokenType::DoublePipe => {
+                self.check_expr(left, Some(&Type::Bool))?;
+                self.check_expr(right, Some(&Type::Bool))?;
+
+                Ok(Type::Bool)
             }
 
-            ASTNode::Expression { token: _ } => {
+            TokenType::DoubleLeftAngle | TokenType::DoubleRightAngle => {
+                let ty = self.check_expr(left, expected.filter(|t| t.is_integer()))?;
+                let amount = self.check_expr(right, None)?;
+
+                self.expect_operand(operator, false, &ty, Type::is_integer, left.span())?;
+                self.expect_operand(operator, false, &amount, Type::is_integer, right.span())?;
+
+                Ok(ty)
+            }
+
+            TokenType::DoubleEqual
+            | TokenType::ExclamEqual
+            | TokenType::LeftAngle
+            | TokenType::RightAngle
+            | TokenType::LessEqual
+            | TokenType::GreaterEqual => {
+                let ty = self.check_operands(left, right, None)?;
+                let is_ordered = !matches!(operator.token_type, TokenType::DoubleEqual | TokenType::ExclamEqual);
+
+                self.expect_operand(
+                    operator,
+                    false,
+                    &ty,
+                    |ty| ty.is_numeric() || *ty == Type::Char || (*ty == Type::Bool && !is_ordered),
+                    left.span().to(right.span()),
+                )?;
+
+                Ok(Type::Bool)
             }
 
-            ASTNode::VariableExpression { name: _ } => {
+            TokenType::Ampersand | TokenType::Pipe | TokenType::Carrot => {
+                let ty = self.check_operands(left, right, expected)?;
+                let span = left.span().to(right.span());
+                self.expect_operand(operator, false, &ty, |ty| ty.is_integer() || *ty == Type::Bool, span)?;
+
+                Ok(ty)
+            }
+
+            _ => {
+                let ty = self.check_operands(left, right, expected)?;
+                self.expect_operand(operator, false, &ty, Type::is_numeric, left.span().to(right.span()))?;
+
+                Ok(ty)
+            }
+        }
+    }
+
+    /// Checks the two operands of an operator that needs them to have the
+    /// same type. An unsuffixed literal takes the type of the other side.
+    fn check_operands(&mut self, left: &ASTNode<'a>, right: &ASTNode<'a>, expected: Option<&Type>) -> Result<Type, Box<Diagnostic>> {
+        if left.is_untyped_literal() && !right.is_untyped_literal() {
+            let ty = self.check_expr(right, expected)?;
+            self.check_expr(left, Some(&ty))?;
+
+            Ok(ty)
+        } else {
+            let ty = self.check_expr(left, expected)?;
+            self.check_expr(right, Some(&ty))?;
+
+            Ok(ty)
+        }
+    }
+
+    fn check_unary(&mut self, operator: &Token<'a>, operand: &ASTNode<'a>, expected: Option<&Type>) -> Result<Type, Box<Diagnostic>> {
+        if operator.token_type == TokenType::ExclamationMark {
+            let ty = self.check_expr(operand, expected)?;
+            self.expect_operand(operator, true, &ty, |ty| ty.is_integer() || *ty == Type::Bool, operand.span())?;
+
+            return Ok(ty);
+        }
+
+        // `-128i8` is in range even though `128i8` is not
+        let ty = match operand {
+            ASTNode::Expression { token } if matches!(token.token_type, TokenType::IntLiteral { .. }) => {
+                self.check_negated_literal(token, expected)?
             }
+            _ => self.check_expr(operand, expected)?,
+        };
+        self.expect_operand(operator, true, &ty, |ty| ty.is_signed() || ty.is_float(), operand.span())?;
 
-            ASTNode::Primtive { token: _ } => {
+        Ok(ty)
+    }
+
+    fn check_negated_literal(&self, token: &Token<'a>, expected: Option<&Type>) -> Result<Type, Box<Diagnostic>> {
+        let TokenType::IntLiteral { value, suffix } = &token.token_type else {
+            unreachable!("only called for integer literals");
+        };
+
+        let ty = match (suffix, expected) {
+            (Some(suffix), _) => Type::from(*suffix),
+            (None, Some(expected)) if expected.is_integer() => expected.clone(),
+            (None, _) => Type::I32,
+        };
+
+        if let Some((min, max)) = ty.int_range() {
+            if ty.is_signed() && *value > min.unsigned_abs() {
+                return Err(out_of_range(token, &ty)
+                    .with_note(format!("the type `{}` can only hold values in `{}..={}`", ty, min, max)).into());
             }
         }
 
+        Ok(ty)
+    }
+
+    /// Checks that `ty`, the type of the operand at `span`, is valid for the
+    /// unary or binary `operator`.
+    fn expect_operand(
+        &self,
+        operator: &Token<'a>,
+        is_unary: bool,
+        ty: &Type,
+        is_valid: impl Fn(&Type) -> bool,
+        span: Span,
+    ) -> Result<(), Box<Diagnostic>> {
+        if is_valid(ty) {
+            return Ok(());
+        }
+
+        let message = if is_unary {
+            format!("cannot apply unary operator `{}` to type `{}`", operator.lexeme, ty)
+        } else {
+            format!("binary operation `{}` cannot be applied to type `{}`", operator.lexeme, ty)
+        };
+
+        Err(Diagnostic::error(codes::INVALID_OPERAND, message)
+            .with_span(span)
+            .with_label(operator.span, "operator").into())
+    }
+
+    /// Checks the statements of a block in a scope of their own and returns
+    /// the type of its value, `void` when it has none.
+    fn check_block(&mut self, block: &ASTNode<'a>, expected: Option<&Type>) -> Result<Type, Box<Diagnostic>> {
+        let ASTNode::Block { statements, value, close, .. } = block else {
+            unreachable!("only called for blocks");
+        };
+
+        self.scopes.push(HashMap::new());
+        for stmt in statements {
+            self.check_node(stmt)?;
+        }
+
+        let ty = match value {
+            Some(value) => self.check_expr(value, expected)?,
+            None => {
+                if let Some(expected) = expected {
+                    self.expect_type(expected, &Type::Void, close.span)?;
+                }
+
+                Type::Void
+            }
+        };
+        self.scopes.pop();
+
+        Ok(ty)
+    }
+
+    fn check_assign(&mut self, target: &ASTNode<'a>, op: &Token<'a>, value: &ASTNode<'a>) -> Result<(), Box<Diagnostic>> {
+        let ty = self.check_place(target)?;
+
+        match op.token_type.compound_operator() {
+            None => {
+                self.check_expr(value, Some(&ty))?;
+            }
+
+            Some(TokenType::DoubleLeftAngle | TokenType::DoubleRightAngle) => {
+                let amount = self.check_expr(value, None)?;
+
+                self.expect_operand(op, false, &ty, Type::is_integer, target.span())?;
+                self.expect_operand(op, false, &amount, Type::is_integer, value.span())?;
+            }
+
+            Some(operator) => {
+                self.check_expr(value, Some(&ty))?;
+
+                let is_bitwise = matches!(operator, TokenType::Ampersand | TokenType::Pipe | TokenType::Carrot);
+                let is_valid = |ty: &Type| {
+                    if is_bitwise { ty.is_integer() || *ty == Type::Bool } else { ty.is_numeric() }
+                };
+                self.expect_operand(op, false, &ty, is_valid, target.span())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the type of `target`, recording an error if it cannot be
+    /// assigned to. A variable must not be bound with `const`; an array
+    /// element must not be `const` in its array's type. The two are
+    /// independent, so the elements of a `const` array may still change.
+    fn check_place(&mut self, target: &ASTNode<'a>) -> Result<Type, Box<Diagnostic>> {
+        if let ASTNode::Index { target: array, .. } = target {
+            let ty = self.check_expr(target, None)?;
+            self.check_elements_mutable(array, target)?;
+
+            return Ok(ty);
+        }
+
+        let ASTNode::VariableExpression { name } = target else {
+            return self.check_expr(target, None);
+        };
+
+        let binding = self.lookup_binding(name)?;
+        let ty = binding.ty.clone();
+        let mut errors = Vec::new();
+        if binding.is_const {
+            errors.push(Diagnostic::error(
+                codes::ASSIGN_TO_CONST,
+                format!("cannot assign to `{}`, which is declared `const`", name.lexeme),
+            )
+            .with_span(name.span)
+            .with_label(binding.span, "declared `const` here")
+            .with_help("declare it with `let` to allow assignment"));
+        }
+        // an `anysize` array refers to the caller's elements, so there is
+        // nowhere to store a whole new array
+        if let Type::Array { len: None, .. } = ty {
+            errors.push(Diagnostic::error(
+                codes::ASSIGN_TO_ANYSIZE,
+                format!("cannot assign to `{}`, which is an `anysize` array", name.lexeme),
+            )
+            .with_span(name.span)
+            .with_label(binding.span, format!("declared as `{}` here", ty))
+            .with_help("assign to its elements instead"));
+        }
+        self.errors.extend(errors);
+
+        Ok(ty)
+    }
+
+    /// Records an error for `place` unless the elements of `array` can be
+    /// assigned to. When `array` is itself an element, changing it changes the
+    /// array holding it, so that array's elements are checked as well.
+    fn check_elements_mutable(&mut self, array: &ASTNode<'a>, place: &ASTNode<'a>) -> Result<(), Box<Diagnostic>> {
+        let ty = self.check_expr(array, None)?;
+        if let Type::Array { const_elements: true, .. } = ty {
+            let mut error = match array {
+                ASTNode::VariableExpression { name } => {
+                    let binding = self.lookup_binding(name)?;
+
+                    Diagnostic::error(
+                        codes::ASSIGN_TO_CONST_ELEMENT,
+                        format!("cannot assign to an element of `{}`, whose elements are `const`", name.lexeme),
+                    )
+                    .with_label(binding.span, format!("declared as `{}` here", ty))
+                }
+                _ => Diagnostic::error(codes::ASSIGN_TO_CONST_ELEMENT, "cannot assign to a `const` array element")
+                    .with_label(array.span(), format!("this array is a `{}`", ty)),
+            };
+            error = error
+                .with_span(place.span())
+                .with_help("remove `const` from the element type to allow changing the elements");
+            self.errors.push(error);
+
+            return Ok(());
+        }
+
+        if let ASTNode::Index { target, .. } = array {
+            self.check_elements_mutable(target, place)?;
+        }
+
         Ok(())
     }
 
-    fn validate_type(&self, type_name: &str) -> Result<(), String> {
-        if self.is_primitive(type_name) {
+    fn check_call(&mut self, name: &Token<'a>, arguments: &[ASTNode<'a>]) -> Result<Type, Box<Diagnostic>> {
+        if name.lexeme == "println" {
+            for arg in arguments {
+                self.check_expr(arg, None)?;
+            }
+
+            return Ok(Type::Void);
+        }
+
+        let Some(signature) = self.functions.get(name.lexeme) else {
+            return Err(Diagnostic::error(codes::UNDEFINED_FUNCTION, format!("cannot find function `{}`", name.lexeme))
+                .with_span(name.span).into());
+        };
+
+        if signature.parameters.len() != arguments.len() {
+            return Err(Diagnostic::error(
+                codes::ARGUMENT_COUNT,
+                format!(
+                    "function `{}` takes {} argument(s) but {} were supplied",
+                    name.lexeme,
+                    signature.parameters.len(),
+                    arguments.len(),
+                ),
+            )
+            .with_span(name.span).into());
+        }
+
+        let parameters = signature.parameters.clone();
+        let return_type = signature.return_type.clone();
+        for (arg, param) in arguments.iter().zip(&parameters) {
+            self.check_expr(arg, Some(param))?;
+        }
+
+        Ok(return_type)
+    }
+
+    fn expect_type(&self, expected: &Type, found: &Type, span: Span) -> Result<(), Box<Diagnostic>> {
+        let shared = match (expected, found) {
+            (
+                Type::Array { element, len: None, const_elements: false },
+                Type::Array { element: found_element, const_elements: true, .. },
+            ) if element == found_element => Some(Type::Array { element: element.clone(), len: None, const_elements: true }),
+            _ => None,
+        };
+
+        if expected.accepts(found) {
             Ok(())
+        } else if let Some(shared) = shared {
+            Err(Diagnostic::error(
+                codes::CONST_ELEMENTS_DISCARDED,
+                format!("cannot use `{}` as `{}`, which allows changing its elements", found, expected),
+            )
+            .with_span(span)
+            .with_note("an `anysize` array shares its elements rather than copying them")
+            .with_help(format!("take `{}` instead", shared)).into())
         } else {
-            Err(format!("error: unknown type '{}'", type_name))
+            Err(Diagnostic::error(codes::MISMATCHED_TYPES, "mismatched types")
+                .with_span(span)
+                .with_note(format!("expected `{}`, found `{}`", expected, found)).into())
         }
     }
 
-    fn is_primitive(&self, type_name: &str) -> bool {
-        matches!(type_name,
-            "i8" | "i16" | "i32" | "i64" | "isize" |
-            "u8" | "u16" | "u32" | "u64" | "usize" |
-            "f32" | "f64" |
-            "bool" | "char" | "void"
-        )
+    fn declare(&mut self, name: &Token<'a>, ty: Type, is_const: bool) {
+        self.scopes
+            .last_mut()
+            .expect("type checker always has a scope")
+            .insert(name.lexeme, Binding { ty, is_const, span: name.span });
+    }
+
+    fn lookup(&self, name: &Token<'a>) -> Result<Type, Box<Diagnostic>> {
+        self.lookup_binding(name).map(|binding| binding.ty.clone())
     }
+
+    fn lookup_binding(&self, name: &Token<'a>) -> Result<&Binding, Box<Diagnostic>> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(name.lexeme))
+            .ok_or_else(|| {
+                Diagnostic::error(codes::UNDEFINED_VARIABLE, format!("cannot find value `{}` in this scope", name.lexeme))
+                    .with_span(name.span)
+                    .into()
+            })
+    }
+
+    fn resolve_type(&self, type_expr: &TypeExpr<'a>) -> Result<Type, Box<Diagnostic>> {
+        let mut current = type_expr;
+        while let TypeExpr::Array(array) = current {
+            if array.size.token_type == TokenType::AnySize {
+                return Err(Diagnostic::error(codes::ANYSIZE_NOT_ALLOWED, "`anysize` is only allowed in parameter types")
+                    .with_span(array.size.span)
+                    .with_help("give the array a fixed size").into());
+            }
+            if let TokenType::IntLiteral { value, .. } = array.size.token_type {
+                if value > u32::MAX as u128 {
+                    return Err(Diagnostic::error(codes::ARRAY_TOO_LARGE, format!("array size {} is too large", value))
+                        .with_span(array.size.span)
+                        .with_note(format!("an array holds at most {} elements", u32::MAX)).into());
+                }
+            }
+
+            current = &array.element;
+        }
+
+        Type::from_expr(type_expr).map_err(|type_tok| {
+            Diagnostic::error(codes::UNKNOWN_TYPE, format!("unknown type '{}'", type_tok.lexeme))
+                .with_span(type_tok.span)
+                .into()
+        })
+    }
+
+    /// Like `resolve_type`, but the outermost array may be `anysize`.
+    fn resolve_parameter_type(&self, type_expr: &TypeExpr<'a>) -> Result<Type, Box<Diagnostic>> {
+        match type_expr {
+            TypeExpr::Array(array) if array.size.token_type == TokenType::AnySize => Ok(Type::Array {
+                element: Box::new(self.resolve_type(&array.element)?),
+                len: None,
+                const_elements: array.const_elements,
+            }),
+            _ => self.resolve_type(type_expr),
+        }
+    }
+}
+
+/// Numbers convert to each other, and `bool` and `char` convert to integers;
+/// only `u8` converts to `char`.
+fn is_valid_cast(source: &Type, target: &Type) -> bool {
+    source == target
+        || (source.is_numeric() && target.is_numeric())
+        || (matches!(source, Type::Bool | Type::Char) && target.is_integer())
+        || (*source == Type::U8 && *target == Type::Char)
+}
+
+fn out_of_range(token: &Token, ty: &Type) -> Diagnostic {
+    Diagnostic::error(codes::LITERAL_OUT_OF_RANGE, format!("literal out of range for `{}`", ty))
+        .with_span(token.span)
 }
@@ -0,0 +1,197 @@
+use std::fmt;
+
+use lexer::{NumericSuffix, Token, TokenType};
+
+use crate::ast::TypeExpr;
+
+/// A resolved Hydra type, as seen by the type checker and code generator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Type {
+    ISize,
+    I8,
+    I16,
+    I32,
+    I64,
+    USize,
+    U8,
+    U16,
+    U32,
+    U64,
+    F32,
+    F64,
+    Char,
+    Bool,
+    Str,
+    Void,
+    /// `[T, N]`, or `[T, anysize]` when `len` is `None`, which only
+    /// parameters can have.
+    Array {
+        element: Box<Type>,
+        len: Option<u64>,
+        const_elements: bool,
+    },
+}
+
+impl Type {
+    pub fn from_name(name: &str) -> Option<Type> {
+        Some(match name {
+            "isize" => Type::ISize,
+            "i8" => Type::I8,
+            "i16" => Type::I16,
+            "i32" => Type::I32,
+            "i64" => Type::I64,
+            "usize" => Type::USize,
+            "u8" => Type::U8,
+            "u16" => Type::U16,
+            "u32" => Type::U32,
+            "u64" => Type::U64,
+            "f32" => Type::F32,
+            "f64" => Type::F64,
+            "char" => Type::Char,
+            "bool" => Type::Bool,
+            "void" => Type::Void,
+            _ => return None,
+        })
+    }
+
+    /// Resolves a written type, or returns the token of a name that is not a
+    /// type or of an array size that does not fit in a `u64`.
+    pub fn from_expr<'e, 'a>(expr: &'e TypeExpr<'a>) -> Result<Type, &'e Token<'a>> {
+        match expr {
+            TypeExpr::Named(token) => Type::from_name(token.lexeme).ok_or(token),
+            TypeExpr::Array(array) => {
+                let len = match array.size.token_type {
+                    TokenType::IntLiteral { value, .. } => Some(u64::try_from(value).map_err(|_| &array.size)?),
+                    _ => None,
+                };
+
+                Ok(Type::Array {
+                    element: Box::new(Type::from_expr(&array.element)?),
+                    len,
+                    const_elements: array.const_elements,
+                })
+            }
+        }
+    }
+
+    /// Whether a value of type `found` can be used where `self` is expected.
+    /// Besides the same type, an array fits an `anysize` array of its
+    /// elements. A fixed-size array is copied, so the copy's elements may be
+    /// `const` or not; an `anysize` array shares its elements with the
+    /// caller, so it may add `const` to them but not drop it.
+    pub fn accepts(&self, found: &Type) -> bool {
+        match (self, found) {
+            (
+                Type::Array { element, len, const_elements },
+                Type::Array { element: found_element, len: found_len, const_elements: found_const },
+            ) => {
+                element == found_element
+                    && (len.is_none() || len == found_len)
+                    && (*const_elements || !found_const || len.is_some())
+            }
+            _ => self == found,
+        }
+    }
+
+    pub fn is_integer(&self) -> bool {
+        self.bit_width().is_some() && !self.is_float()
+    }
+
+    pub fn is_signed(&self) -> bool {
+        matches!(self, Type::ISize | Type::I8 | Type::I16 | Type::I32 | Type::I64)
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, Type::F32 | Type::F64)
+    }
+
+    pub fn is_numeric(&self) -> bool {
+        self.bit_width().is_some()
+    }
+
+    /// The width in bits of a numeric type. `isize` and `usize` are 64 bits.
+    pub fn bit_width(&self) -> Option<u32> {
+        match self {
+            Type::I8 | Type::U8 => Some(8),
+            Type::I16 | Type::U16 => Some(16),
+            Type::I32 | Type::U32 | Type::F32 => Some(32),
+            Type::I64 | Type::U64 | Type::ISize | Type::USize | Type::F64 => Some(64),
+            _ => None,
+        }
+    }
+
+    /// The inclusive range of values an integer type can hold.
+    pub fn int_range(&self) -> Option<(i128, i128)> {
+        if !self.is_integer() {
+            return None;
+        }
+
+        let bits = self.bit_width()?;
+        if self.is_signed() {
+            Some((-(1i128 << (bits - 1)), (1i128 << (bits - 1)) - 1))
+        } else {
+            Some((0, (1i128 << bits) - 1))
+        }
+    }
+
+    /// The type of the elements `foreach` yields from a value of this type,
+    /// or `None` if it does not take part in the iteration protocol. Only
+    /// arrays do so far.
+    pub fn element_type(&self) -> Option<Type> {
+        match self {
+            Type::Array { element, .. } => Some((**element).clone()),
+            _ => None,
+        }
+    }
+}
+
+impl From<NumericSuffix> for Type {
+    fn from(suffix: NumericSuffix) -> Self {
+        match suffix {
+            NumericSuffix::ISize => Type::ISize,
+            NumericSuffix::I8 => Type::I8,
+            NumericSuffix::I16 => Type::I16,
+            NumericSuffix::I32 => Type::I32,
+            NumericSuffix::I64 => Type::I64,
+            NumericSuffix::USize => Type::USize,
+            NumericSuffix::U8 => Type::U8,
+            NumericSuffix::U16 => Type::U16,
+            NumericSuffix::U32 => Type::U32,
+            NumericSuffix::U64 => Type::U64,
+            NumericSuffix::F32 => Type::F32,
+            NumericSuffix::F64 => Type::F64,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Type::Array { element, len, const_elements } => {
+                let constness = if *const_elements { "const " } else { "" };
+                return match len {
+                    Some(len) => write!(f, "[{}{}, {}]", constness, element, len),
+                    None => write!(f, "[{}{}, anysize]", constness, element),
+                };
+            }
+            Type::ISize => "isize",
+            Type::I8 => "i8",
+            Type::I16 => "i16",
+            Type::I32 => "i32",
+            Type::I64 => "i64",
+            Type::USize => "usize",
+            Type::U8 => "u8",
+            Type::U16 => "u16",
+            Type::U32 => "u32",
+            Type::U64 => "u64",
+            Type::F32 => "f32",
+            Type::F64 => "f64",
+            Type::Char => "char",
+            Type::Bool => "bool",
+            Type::Str => "string",
+            Type::Void => "void",
+        };
+
+        write!(f, "{}", name)
+    }
+}